@@ -0,0 +1,489 @@
+use crate::{
+  conventional::{parse_commit, ConventionalCommit},
+  git::Commit,
+  remote::Remote,
+  utils::{file_exists, open_file, read_file, today, write_file},
+  version::Version,
+};
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// Template used to render the changelog, create it to customize the release notes.
+///
+/// Every `{{name}}` placeholder is replaced by its value and every
+/// `{{#name}}...{{/name}}` block is only kept when the value isn't empty.
+pub const TEMPLATE: &str = ".mentor/changelog.md";
+
+/// Used whenever the repository doesn't have its own template.
+pub const DEFAULT_TEMPLATE: &str = "{{#breaking}}
+### Breaking Changes
+
+{{breaking}}
+{{/breaking}}
+{{#features}}
+### Features
+
+{{features}}
+{{/features}}
+{{#fixes}}
+### Fixes
+
+{{fixes}}
+{{/fixes}}
+{{#performance}}
+### Performance
+
+{{performance}}
+{{/performance}}
+{{#others}}
+### Other Changes
+
+{{others}}
+{{/others}}
+{{#compare_url}}
+**Full Changelog**: {{compare_url}}
+{{/compare_url}}
+";
+
+/// Commits of a release grouped by their type.
+#[derive(Default)]
+struct Groups {
+  breaking: Vec<String>,
+  features: Vec<String>,
+  fixes: Vec<String>,
+  performance: Vec<String>,
+  others: Vec<String>,
+}
+
+/// Formats a single line of the changelog, linking the commit and crediting its author.
+fn entry(repository_url: Option<&str>, commit: &Commit, description: &str) -> String {
+  let hash = match repository_url {
+    Some(repository_url) => format!(
+      "[{}]({repository_url}/commit/{})",
+      commit.short_hash, commit.hash
+    ),
+    None => commit.short_hash.clone(),
+  };
+
+  format!("* {description} ({hash}) by {}", commit.author)
+}
+
+fn group_commits(repository_url: Option<&str>, commits: &[Commit]) -> Groups {
+  let mut groups = Groups::default();
+
+  // Oldest commits first, as they were made.
+  for commit in commits.iter().rev() {
+    let Some(ConventionalCommit {
+      kind,
+      scope,
+      description,
+      breaking,
+    }) = parse_commit(commit)
+    else {
+      groups
+        .others
+        .push(entry(repository_url, commit, &commit.subject));
+      continue;
+    };
+
+    let description = match scope {
+      Some(scope) => format!("**{scope}:** {description}"),
+      None => description,
+    };
+
+    let entry = entry(repository_url, commit, &description);
+
+    if breaking {
+      groups.breaking.push(entry);
+    }
+    else {
+      match kind.as_str() {
+        "feat" => groups.features.push(entry),
+        "fix" => groups.fixes.push(entry),
+        "perf" => groups.performance.push(entry),
+        _ => groups.others.push(entry),
+      }
+    }
+  }
+
+  groups
+}
+
+/// Replaces the placeholders and blocks of the template with the given variables.
+fn render(template: &str, variables: &[(&str, String)]) -> String {
+  let mut output = template.to_string();
+
+  for (name, value) in variables {
+    let open = format!("{{{{#{name}}}}}");
+    let close = format!("{{{{/{name}}}}}");
+
+    while let Some(start) = output.find(&open) {
+      let Some(end) = output[start..].find(&close).map(|end| start + end)
+      else {
+        break;
+      };
+
+      let block = if value.is_empty() {
+        String::new()
+      }
+      else {
+        output[start + open.len()..end].to_string()
+      };
+
+      output.replace_range(start..end + close.len(), &block);
+    }
+
+    output = output.replace(&format!("{{{{{name}}}}}"), value);
+  }
+
+  // Removed blocks leave empty lines behind them.
+  let blank_lines = Regex::new(r"\n{3,}").unwrap();
+  blank_lines.replace_all(output.trim(), "\n\n").to_string()
+}
+
+fn read_template() -> Result<String> {
+  if !file_exists(TEMPLATE) {
+    return Ok(DEFAULT_TEMPLATE.to_string());
+  }
+
+  read_file(&mut open_file(TEMPLATE)?).with_context(|| format!("failed to read '{TEMPLATE}'"))
+}
+
+/// Generates the changelog of the release from the commits made since the previous one.
+/// Commits and releases are only linked when the `remote` is known.
+pub fn generate(
+  remote: Option<&Remote>,
+  commits: &[Commit],
+  previous_version: &Version,
+  previous_tag: Option<&str>,
+  version: &Version,
+  tag: &str,
+) -> Result<String> {
+  let template = read_template()?;
+  let repository_url = remote.map(Remote::web_url);
+
+  let compare_url = match (&repository_url, previous_tag) {
+    (Some(repository_url), Some(previous_tag)) => {
+      format!("{repository_url}/compare/{previous_tag}...{tag}")
+    }
+    _ => String::new(),
+  };
+
+  let groups = group_commits(repository_url.as_deref(), commits);
+
+  let variables = [
+    ("version", version.to_string()),
+    ("previous_version", previous_version.to_string()),
+    ("date", today()),
+    ("compare_url", compare_url),
+    ("breaking", groups.breaking.join("\n")),
+    ("features", groups.features.join("\n")),
+    ("fixes", groups.fixes.join("\n")),
+    ("performance", groups.performance.join("\n")),
+    ("others", groups.others.join("\n")),
+  ];
+
+  Ok(render(&template, &variables))
+}
+
+pub const CHANGELOG_MD: &str = "CHANGELOG.md";
+
+/// Used when the repository doesn't have a `CHANGELOG.md` yet.
+const CHANGELOG_HEADER: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+## [Unreleased]
+";
+
+/// Whether the line is a link reference definition, e.g. `[1.0.0]: https://...`.
+fn is_link_definition(line: &str) -> bool {
+  let link = Regex::new(r"^\[[^\]]+\]:\s*\S+").unwrap();
+  link.is_match(line.trim())
+}
+
+/// Prepends the section of the release to the changelog at `path`, usually `CHANGELOG.md`,
+/// following Keep a Changelog. https://keepachangelog.com/en/1.1.0/
+///
+/// Entries of the `## [Unreleased]` section are moved into the new section,
+/// above the generated `notes`, and compare links at the bottom are updated.
+pub fn update_changelog_file(
+  path: &str,
+  remote: Option<&Remote>,
+  notes: &str,
+  previous_tag: Option<&str>,
+  version: &Version,
+  tag: &str,
+) -> Result<()> {
+  let content = if file_exists(path) {
+    read_file(&mut open_file(path)?)?
+  }
+  else {
+    CHANGELOG_HEADER.to_string()
+  };
+
+  let content = prepend_release(
+    &content,
+    remote,
+    notes,
+    previous_tag,
+    version,
+    tag,
+    &today(),
+  );
+
+  write_file(path, content)
+}
+
+/// Returns the changelog `content` with the section of the release dated `date`.
+fn prepend_release(
+  content: &str,
+  remote: Option<&Remote>,
+  notes: &str,
+  previous_tag: Option<&str>,
+  version: &Version,
+  tag: &str,
+  date: &str,
+) -> String {
+  let mut lines = content.lines().collect::<Vec<_>>();
+
+  // Link definitions are at the very bottom of the file.
+  let mut links = Vec::new();
+  while let Some(line) = lines.last() {
+    if line.trim().is_empty() {
+      lines.pop();
+    }
+    else if is_link_definition(line) {
+      links.insert(0, lines.pop().unwrap().to_string());
+    }
+    else {
+      break;
+    }
+  }
+
+  let is_unreleased = |line: &&str| line.trim().to_lowercase().starts_with("## [unreleased]");
+
+  let (start, unreleased) = match lines.iter().position(is_unreleased) {
+    Some(index) => {
+      let end = lines[index + 1..]
+        .iter()
+        .position(|line| line.starts_with("## "))
+        .map_or(lines.len(), |end| index + 1 + end);
+
+      let unreleased = lines[index + 1..end].join("\n").trim().to_string();
+      lines.drain(index..end);
+
+      (index, unreleased)
+    }
+    // Insert above the latest release, or at the end when there's none.
+    None => (
+      lines
+        .iter()
+        .position(|line| line.starts_with("## "))
+        .unwrap_or(lines.len()),
+      String::new(),
+    ),
+  };
+
+  let mut section = format!("## [Unreleased]\n\n## [{version}] - {date}\n");
+  for part in [unreleased.as_str(), notes.trim()] {
+    if !part.is_empty() {
+      section += &format!("\n{part}\n");
+    }
+  }
+
+  let mut content = lines[..start].join("\n").trim_end().to_string();
+  if !content.is_empty() {
+    content += "\n\n";
+  }
+  content += &section;
+
+  let rest = lines[start..].join("\n");
+  if !rest.trim().is_empty() {
+    content += &format!("\n{}", rest.trim());
+  }
+
+  // Compare links, the latest release first.
+  if let Some(remote) = remote {
+    let repository_url = remote.web_url();
+
+    let release_url = match previous_tag {
+      Some(previous_tag) => format!("{repository_url}/compare/{previous_tag}...{tag}"),
+      None => format!("{repository_url}/releases/tag/{tag}"),
+    };
+
+    links.retain(|link| {
+      let link = link.to_lowercase();
+      !link.starts_with("[unreleased]:") && !link.starts_with(&format!("[{version}]:"))
+    });
+
+    links.insert(0, format!("[{version}]: {release_url}"));
+    links.insert(
+      0,
+      format!("[unreleased]: {repository_url}/compare/{tag}...HEAD"),
+    );
+  }
+
+  let mut content = content.trim_end().to_string();
+  if !links.is_empty() {
+    content += &format!("\n\n{}", links.join("\n"));
+  }
+
+  content + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::version::string_to_version;
+
+  fn remote() -> Remote {
+    Remote {
+      host: "github.com".into(),
+      owner: "owner".into(),
+      repo: "repo".into(),
+    }
+  }
+
+  fn prepend(content: &str, remote: Option<&Remote>, previous_tag: Option<&str>) -> String {
+    prepend_release(
+      content,
+      remote,
+      "### Features\n\n* something\n",
+      previous_tag,
+      &string_to_version("1.1.0").unwrap(),
+      "v1.1.0",
+      "2024-05-01",
+    )
+  }
+
+  #[test]
+  fn prepends_to_a_new_changelog() {
+    assert_eq!(
+      prepend(CHANGELOG_HEADER, Some(&remote()), None),
+      "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+## [Unreleased]
+
+## [1.1.0] - 2024-05-01
+
+### Features
+
+* something
+
+[unreleased]: https://github.com/owner/repo/compare/v1.1.0...HEAD
+[1.1.0]: https://github.com/owner/repo/releases/tag/v1.1.0
+"
+    );
+  }
+
+  #[test]
+  fn moves_unreleased_entries_into_the_release() {
+    let content = "# Changelog
+
+## [Unreleased]
+
+### Changed
+
+- Written by hand.
+
+## [1.0.0] - 2024-01-01
+
+- First release.
+
+[Unreleased]: https://github.com/owner/repo/compare/v1.0.0...HEAD
+[1.0.0]: https://github.com/owner/repo/releases/tag/v1.0.0
+";
+
+    assert_eq!(
+      prepend(content, Some(&remote()), Some("v1.0.0")),
+      "# Changelog
+
+## [Unreleased]
+
+## [1.1.0] - 2024-05-01
+
+### Changed
+
+- Written by hand.
+
+### Features
+
+* something
+
+## [1.0.0] - 2024-01-01
+
+- First release.
+
+[unreleased]: https://github.com/owner/repo/compare/v1.1.0...HEAD
+[1.1.0]: https://github.com/owner/repo/compare/v1.0.0...v1.1.0
+[1.0.0]: https://github.com/owner/repo/releases/tag/v1.0.0
+"
+    );
+  }
+
+  #[test]
+  fn replaces_the_unreleased_link() {
+    let content = "## [Unreleased]
+
+- Written by hand.
+
+[unreleased]: https://github.com/owner/repo/compare/v1.0.0...main
+[1.0.0]: https://github.com/owner/repo/releases/tag/v1.0.0
+";
+
+    assert_eq!(
+      prepend(content, Some(&remote()), Some("v1.0.0")),
+      "## [Unreleased]
+
+## [1.1.0] - 2024-05-01
+
+- Written by hand.
+
+### Features
+
+* something
+
+[unreleased]: https://github.com/owner/repo/compare/v1.1.0...HEAD
+[1.1.0]: https://github.com/owner/repo/compare/v1.0.0...v1.1.0
+[1.0.0]: https://github.com/owner/repo/releases/tag/v1.0.0
+"
+    );
+  }
+
+  #[test]
+  fn inserts_above_the_latest_release_without_unreleased() {
+    let content = "# Changelog
+
+## [1.0.0] - 2024-01-01
+
+- First release.
+";
+
+    assert_eq!(
+      prepend(content, None, Some("v1.0.0")),
+      "# Changelog
+
+## [Unreleased]
+
+## [1.1.0] - 2024-05-01
+
+### Features
+
+* something
+
+## [1.0.0] - 2024-01-01
+
+- First release.
+"
+    );
+  }
+}
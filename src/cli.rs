@@ -1,21 +1,322 @@
-use crate::version::{bump_version, string_to_version, version_to_string};
-use dialoguer::Select;
+use crate::version::{
+  bump_version, is_valid_channel, string_to_version, version_to_string, Bump, Version, CHANNELS,
+};
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use dialoguer::{Confirm, Select};
+use std::io::IsTerminal;
 
-pub fn prompt_new_version(current_version: &str) -> String {
-  let version = string_to_version(current_version);
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Args {
+  #[command(subcommand)]
+  pub command: Option<Command>,
+
+  /// Bump to apply (major, minor, patch, premajor, preminor, prepatch,
+  /// prerelease, release) or the exact version to release.
+  /// When omitted, you'll be prompted for it.
+  #[arg(value_name = "BUMP|VERSION")]
+  pub target: Option<String>,
+
+  /// Pre-release channel used by the premajor, preminor, prepatch
+  /// and prerelease bumps, defaults to the current one or "alpha".
+  #[arg(long, value_parser = parse_channel)]
+  pub channel: Option<String>,
+
+  /// Release the bump recommended from the Conventional Commits
+  /// made since the last release, without prompting for it.
+  #[arg(long, conflicts_with = "target")]
+  pub auto: bool,
+
+  /// Add the release notes to `CHANGELOG.md` in the release commit.
+  #[arg(long)]
+  pub changelog: bool,
+
+  /// Create the GitHub release as a draft, only when created through the API.
+  #[arg(long)]
+  pub draft: bool,
+
+  /// Git remote to push to, and where the GitHub repository is read from,
+  /// defaults to "origin".
+  #[arg(long)]
+  pub remote: Option<String>,
+
+  /// Skip every confirmation.
+  #[arg(short, long)]
+  pub yes: bool,
+
+  /// Show every file edit and git command without running them.
+  #[arg(long, global = true)]
+  pub dry_run: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+  /// Continue a release that was interrupted after being tagged:
+  /// push it and create the GitHub release.
+  Resume,
+}
+
+fn parse_channel(channel: &str) -> Result<String> {
+  if !is_valid_channel(channel) {
+    anyhow::bail!("only ASCII letters, digits and hyphens are allowed, and not only digits");
+  }
+
+  Ok(channel.to_string())
+}
+
+/// Makes sure we can prompt the user, instead of failing in the middle of a prompt.
+fn ensure_interactive() -> Result<()> {
+  if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+    anyhow::bail!(
+      "no TTY available to prompt, pass the version as argument and use '--yes' to run non-interactively"
+    );
+  }
+
+  Ok(())
+}
+
+fn default_channel(version: &Version) -> String {
+  version.channel().unwrap_or(CHANNELS[0]).to_string()
+}
+
+fn bump_label(bump: &Bump) -> &'static str {
+  match bump {
+    Bump::Major => "major",
+    Bump::Minor => "minor",
+    Bump::Patch => "patch",
+    Bump::Premajor(_) => "premajor",
+    Bump::Preminor(_) => "preminor",
+    Bump::Prepatch(_) => "prepatch",
+    Bump::Prerelease(_) => "next prerelease",
+    Bump::Release => "promote to stable",
+  }
+}
+
+/// Resolves the version given as argument, either a bump or an exact version.
+pub fn parse_new_version(
+  input: &str,
+  channel: Option<&str>,
+  version: &Version,
+  tags: &[Version],
+) -> Result<Version> {
+  let channel = channel.map_or_else(|| default_channel(version), String::from);
+
+  let bump = match input {
+    "major" => Bump::Major,
+    "minor" => Bump::Minor,
+    "patch" => Bump::Patch,
+    "premajor" => Bump::Premajor(channel),
+    "preminor" => Bump::Preminor(channel),
+    "prepatch" => Bump::Prepatch(channel),
+    "prerelease" => Bump::Prerelease(channel),
+    "release" => Bump::Release,
+    _ => {
+      let new_version = string_to_version(input)
+        .with_context(|| format!("'{input}' is neither a bump nor a valid version"))?;
+
+      if new_version <= *version {
+        anyhow::bail!("new version {new_version} must be greater than the current {version}");
+      }
+
+      return Ok(new_version);
+    }
+  };
+
+  if bump == Bump::Release && !version.is_prerelease() {
+    anyhow::bail!("{version} is not a pre-release, there's nothing to promote");
+  }
+
+  let new_version = bump_version(version, &bump, tags);
+
+  // e.g. a prerelease on `alpha` from `2.0.0-rc.1` gives `2.0.0-alpha.1`.
+  if new_version <= *version {
+    anyhow::bail!(
+      "{input} bump gives {new_version}, which isn't greater than the current {version}"
+    );
+  }
+
+  Ok(new_version)
+}
+
+/// Only the channels that `allowed` accepts are listed,
+/// i.e. the ones that don't go back to a lower version.
+fn prompt_channel(version: &Version, allowed: impl Fn(&str) -> bool) -> Result<String> {
+  let mut channels = CHANNELS.map(String::from).to_vec();
+
+  // Keep the channel currently in use selectable, even if it's a custom one.
+  let current = default_channel(version);
+  if !channels.contains(&current) {
+    channels.push(current.clone());
+  }
+
+  channels.retain(|channel| allowed(channel));
+
+  if channels.is_empty() {
+    anyhow::bail!("no pre-release channel comes after {version}, pass one with '--channel'");
+  }
+
+  let selection = Select::new()
+    .with_prompt("On which pre-release channel")
+    .items(&channels)
+    .default(
+      channels
+        .iter()
+        .position(|channel| *channel == current)
+        .unwrap_or(0),
+    )
+    .interact()?;
+
+  Ok(channels.swap_remove(selection))
+}
+
+/// `tags` are the versions already released, used to compute pre-release counters.
+/// The `recommended` bump is pre-selected.
+///
+/// The pre-release `channel` given as argument is used as is,
+/// the user is prompted for one otherwise.
+pub fn prompt_new_version(
+  version: &Version,
+  channel: Option<&str>,
+  tags: &[Version],
+  recommended: &Bump,
+) -> Result<Version> {
+  ensure_interactive()?;
+
+  // Without a channel given, it's only used to preview the pre-release versions
+  // and the user picks the real one afterwards.
+  let preview_channel = channel.map_or_else(|| default_channel(version), String::from);
+  let pick_channel = |bump: fn(String) -> Bump| match channel {
+    Some(channel) => Ok(channel.to_string()),
+    None => prompt_channel(version, |channel| {
+      bump_version(version, &bump(channel.into()), tags) > *version
+    }),
+  };
+
+  let mut bumps = vec![
+    Bump::Major,
+    Bump::Minor,
+    Bump::Patch,
+    Bump::Premajor(preview_channel.clone()),
+    Bump::Preminor(preview_channel.clone()),
+    Bump::Prepatch(preview_channel.clone()),
+    Bump::Prerelease(preview_channel),
+  ];
+
+  if version.is_prerelease() {
+    bumps.push(Bump::Release);
+  }
+
+  // Bumps on the given channel can go back to a lower version, e.g. a prerelease
+  // on `alpha` from `2.0.0-rc.1`. The channels are filtered once picked otherwise.
+  bumps.retain(|bump| bump_version(version, bump, tags) > *version);
+
+  let items = bumps
+    .iter()
+    .map(|bump| {
+      let preview = version_to_string(&bump_version(version, bump, tags));
+      let label = format!("{} ({preview})", bump_label(bump));
+
+      if bump == recommended {
+        format!("{label} - recommended")
+      }
+      else {
+        label
+      }
+    })
+    .collect::<Vec<_>>();
+
+  // Default to the recommended bump, when it's listed.
+  let default = bumps
+    .iter()
+    .position(|bump| bump == recommended)
+    .unwrap_or(2);
 
   let selection = Select::new()
     .with_prompt(format!(
-      "We're currently at {current_version}, next bump should be a"
+      "We're currently at {version}, next bump should be a"
     ))
-    .items(&[
-      format!("major ({})", version_to_string(bump_version(&version, 0))),
-      format!("minor ({})", version_to_string(bump_version(&version, 1))),
-      format!("patch ({})", version_to_string(bump_version(&version, 2))),
-    ])
-    .default(2) // default to patch.
-    .interact()
-    .unwrap();
-
-  version_to_string(bump_version(&version, selection))
+    .items(&items)
+    .default(default)
+    .interact()?;
+
+  let bump = match bumps.swap_remove(selection) {
+    Bump::Premajor(_) => Bump::Premajor(pick_channel(Bump::Premajor)?),
+    Bump::Preminor(_) => Bump::Preminor(pick_channel(Bump::Preminor)?),
+    Bump::Prepatch(_) => Bump::Prepatch(pick_channel(Bump::Prepatch)?),
+    Bump::Prerelease(_) => Bump::Prerelease(pick_channel(Bump::Prerelease)?),
+    bump => bump,
+  };
+
+  Ok(bump_version(version, &bump, tags))
+}
+
+/// Asks the user to confirm before anything gets modified.
+pub fn confirm_release(old_version: &Version, new_version: &Version) -> Result<bool> {
+  ensure_interactive()?;
+
+  let confirmed = Confirm::new()
+    .with_prompt(format!("Release {old_version} -> {new_version} ?"))
+    .default(true)
+    .interact()?;
+
+  Ok(confirmed)
+}
+
+/// Asks the user to confirm the release of every package, listed in `summary`.
+pub fn confirm_packages(summary: &[String]) -> Result<bool> {
+  ensure_interactive()?;
+
+  for line in summary {
+    println!("  {line}");
+  }
+
+  let confirmed = Confirm::new()
+    .with_prompt(format!("Release these {} package(s) ?", summary.len()))
+    .default(true)
+    .interact()?;
+
+  Ok(confirmed)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(input: &str, channel: Option<&str>, version: &str) -> Result<String> {
+    let version = string_to_version(version).unwrap();
+    parse_new_version(input, channel, &version, &[]).map(|version| version.to_string())
+  }
+
+  #[test]
+  fn parses_bumps_and_versions() {
+    assert_eq!(parse("minor", None, "1.2.3").unwrap(), "1.3.0");
+    assert_eq!(
+      parse("prerelease", None, "2.0.0-rc.1").unwrap(),
+      "2.0.0-rc.2"
+    );
+    assert_eq!(
+      parse("preminor", Some("beta"), "1.2.3").unwrap(),
+      "1.3.0-beta.1"
+    );
+    assert_eq!(parse("1.5.0", None, "1.2.3").unwrap(), "1.5.0");
+  }
+
+  #[test]
+  fn validates_channels() {
+    assert!(parse_channel("beta").is_ok());
+    assert!(parse_channel("rc-2").is_ok());
+    assert!(parse_channel("").is_err());
+    assert!(parse_channel("foo bar").is_err());
+    assert!(parse_channel("1").is_err());
+    assert!(parse_channel("beta.1").is_err());
+  }
+
+  #[test]
+  fn rejects_going_backwards() {
+    assert!(parse("prerelease", Some("alpha"), "2.0.0-rc.1").is_err());
+    assert!(parse("1.2.3", None, "1.2.3").is_err());
+    assert!(parse("1.0.0", None, "1.2.3").is_err());
+    assert!(parse("release", None, "1.2.3").is_err());
+  }
 }
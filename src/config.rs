@@ -0,0 +1,292 @@
+use crate::{
+  implementations::rust::CARGO_TOML,
+  language::Language,
+  tag::TagTemplate,
+  utils::{file_exists, open_file, read_file},
+};
+use anyhow::{Context, Result};
+use serde::{
+  de::{self, value::SeqAccessDeserializer, IntoDeserializer, SeqAccess, Visitor},
+  Deserialize, Deserializer,
+};
+use std::fmt;
+
+/// Configuration file at the root of the repository.
+pub const MENTOR_TOML: &str = "mentor.toml";
+
+/// Messages and names are templates where `{version}` is replaced by the new version
+/// and `{package}` by the name of the package.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  /// Skips the automatic detection of the languages, either one or a list of them.
+  #[serde(deserialize_with = "one_or_many")]
+  pub language: Vec<Language>,
+  /// Name of the package, required when a template uses `{package}`.
+  pub package: Option<String>,
+  pub versioning: Versioning,
+  pub remote: String,
+  /// With independent versioning, `{version}` is replaced by the tags of the released
+  /// packages and `{package}` by their names. See `Config::commit_message` for the default.
+  pub commit_message: Option<String>,
+  pub tag: String,
+  pub tag_message: String,
+  pub release_name: String,
+  pub steps: Steps,
+  pub rust: RustConfig,
+  pub js: JsConfig,
+  pub kotlin: KotlinConfig,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      language: Vec::new(),
+      package: None,
+      versioning: Versioning::default(),
+      remote: "origin".into(),
+      commit_message: None,
+      tag: "{version}".into(),
+      tag_message: "Release v{version}".into(),
+      release_name: "v{version}".into(),
+      steps: Steps::default(),
+      rust: RustConfig::default(),
+      js: JsConfig::default(),
+      kotlin: KotlinConfig::default(),
+    }
+  }
+}
+
+/// Accepts `language = "rust"` as well as `language = ["rust", "js"]`.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Language>, D::Error> {
+  struct LanguagesVisitor;
+
+  impl<'de> Visitor<'de> for LanguagesVisitor {
+    type Value = Vec<Language>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a language or a list of languages")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+      Language::deserialize(value.into_deserializer()).map(|language| vec![language])
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+      Vec::deserialize(SeqAccessDeserializer::new(seq))
+    }
+  }
+
+  deserializer.deserialize_any(LanguagesVisitor)
+}
+
+/// How the packages of a workspace are versioned.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Versioning {
+  /// Every package shares the same version, released together.
+  #[default]
+  Fixed,
+  /// Every package has its own version, only the changed ones are released,
+  /// each with its own tag.
+  Independent,
+}
+
+/// Steps of the release that can be turned on or off.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Steps {
+  /// Runs the checks of the language: linters, tests...
+  pub checks: bool,
+  /// Makes sure the repository is clean and up to date with the remote.
+  pub status: bool,
+  /// Adds the release notes to `CHANGELOG.md`.
+  pub changelog: bool,
+  pub commit: bool,
+  pub tag: bool,
+  pub push: bool,
+  /// Creates the release on GitHub.
+  pub release: bool,
+}
+
+impl Default for Steps {
+  fn default() -> Self {
+    Self {
+      checks: true,
+      status: true,
+      changelog: false,
+      commit: true,
+      tag: true,
+      push: true,
+      release: true,
+    }
+  }
+}
+
+/// Checks that can be run before releasing a Rust project.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RustCheck {
+  /// `cargo fmt --check`
+  Fmt,
+  /// `cargo clippy -- -D warnings`
+  Clippy,
+  /// `cargo test`
+  Test,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RustConfig {
+  /// Checks to run, in order, when `steps.checks` is enabled.
+  pub checks: Vec<RustCheck>,
+}
+
+impl Default for RustConfig {
+  fn default() -> Self {
+    Self {
+      checks: vec![RustCheck::Fmt, RustCheck::Clippy, RustCheck::Test],
+    }
+  }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JsConfig {
+  /// Scripts of `package.json` to run as checks, when `steps.checks` is enabled.
+  /// Defaults to the `prerelease` script, or to `lint`, `typecheck` and `test`.
+  pub checks: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KotlinConfig {
+  /// Build file containing the `version = "x.y.z"` line.
+  pub build_file: String,
+  /// Maven group of the artifact, used to bump the installation snippets of the README.
+  pub group: String,
+}
+
+impl Default for KotlinConfig {
+  fn default() -> Self {
+    Self {
+      build_file: "library/build.gradle.kts".into(),
+      group: "ink.literate".into(),
+    }
+  }
+}
+
+impl Config {
+  /// Replaces the `{version}` and `{package}` placeholders of the template.
+  pub fn expand(&self, template: &str, version: &str) -> String {
+    let output = template.replace("{version}", version);
+
+    match &self.package {
+      Some(package) => output.replace("{package}", package),
+      None => output,
+    }
+  }
+
+  /// Template of the message of the release commit. With independent versioning,
+  /// `{version}` stands for the released tags so the default doesn't prefix it with `v`.
+  pub fn commit_message(&self) -> &str {
+    match (&self.commit_message, self.versioning) {
+      (Some(commit_message), _) => commit_message,
+      (None, Versioning::Fixed) => "chore: release v{version}",
+      (None, Versioning::Independent) => "chore: release {version}",
+    }
+  }
+
+  /// Template of the tags, used to name new tags and to find the previous ones.
+  pub fn tag_template(&self) -> Result<TagTemplate<'_>> {
+    TagTemplate::new(&self.tag, self.package.as_deref())
+  }
+
+  /// Reads `mentor.toml`, or the `[package.metadata.mentor]` table of `Cargo.toml`
+  /// - `[workspace.metadata.mentor]` also works - defaults are used when none of them exists.
+  pub fn load() -> Result<Self> {
+    let config = if file_exists(MENTOR_TOML) {
+      let content = read_file(&mut open_file(MENTOR_TOML)?)?;
+      toml::from_str(&content)
+        .with_context(|| format!("invalid configuration in '{MENTOR_TOML}'"))?
+    }
+    else if let Some(metadata) = Self::cargo_metadata()? {
+      metadata
+        .try_into()
+        .with_context(|| format!("invalid configuration in '{CARGO_TOML}' metadata"))?
+    }
+    else {
+      Self::default()
+    };
+
+    config.validate()?;
+
+    Ok(config)
+  }
+
+  fn cargo_metadata() -> Result<Option<toml::Value>> {
+    if !file_exists(CARGO_TOML) {
+      return Ok(None);
+    }
+
+    let content = read_file(&mut open_file(CARGO_TOML)?)?;
+    let content: toml::Value = toml::from_str(&content)?;
+
+    // Virtual workspaces don't have a `[package]` table.
+    let metadata = ["package", "workspace"]
+      .iter()
+      .find_map(|table| content.get(table)?.get("metadata")?.get("mentor"))
+      .cloned();
+
+    Ok(metadata)
+  }
+
+  fn validate(&self) -> Result<()> {
+    // Tags have to be unique for every release, and the version is read back from them.
+    if self.tag.matches("{version}").count() != 1 {
+      anyhow::bail!("'tag' must contain the '{{version}}' placeholder exactly once");
+    }
+
+    let templates = [
+      self.commit_message(),
+      &self.tag,
+      &self.tag_message,
+      &self.release_name,
+    ];
+
+    // Tags are named after the packages of the workspace with independent versioning.
+    if self.versioning == Versioning::Independent {
+      if !self.tag.contains("{package}") {
+        anyhow::bail!(
+          "'tag' must contain the '{{package}}' placeholder with independent versioning, e.g. \"{{package}}@{{version}}\""
+        );
+      }
+    }
+    else if self.package.is_none()
+      && templates
+        .iter()
+        .any(|template| template.contains("{package}"))
+    {
+      anyhow::bail!("'package' must be set to use the '{{package}}' placeholder");
+    }
+
+    if self
+      .tag
+      .chars()
+      .any(|c| c.is_whitespace() || "~^:?*[\\".contains(c))
+    {
+      anyhow::bail!("'tag' contains characters that aren't allowed in git tags");
+    }
+
+    if self.remote.is_empty() {
+      anyhow::bail!("'remote' can't be empty");
+    }
+
+    // A release is made from a pushed tag.
+    if self.steps.release && !(self.steps.tag && self.steps.push) {
+      anyhow::bail!("'steps.release' needs both 'steps.tag' and 'steps.push' to be enabled");
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,147 @@
+use crate::{
+  git::Commit,
+  version::{Bump, Version},
+};
+use regex::Regex;
+use std::{fmt, sync::LazyLock};
+
+/// A commit message following the Conventional Commits specification.
+/// https://www.conventionalcommits.org/en/v1.0.0/
+pub struct ConventionalCommit {
+  pub kind: String,
+  pub scope: Option<String>,
+  pub description: String,
+  /// Either marked with `!` after the type/scope or with a `BREAKING CHANGE:` footer.
+  pub breaking: bool,
+}
+
+/// Matches `type(scope)!: description` where the scope and `!` are optional.
+static HEADER: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"^(?<kind>[a-zA-Z]+)(?:\((?<scope>[^()]*)\))?(?<breaking>!)?: (?<description>.+)$")
+    .unwrap()
+});
+
+/// Parses the commit, returns `None` when it doesn't follow the specification.
+pub fn parse_commit(commit: &Commit) -> Option<ConventionalCommit> {
+  let captures = HEADER.captures(commit.subject.trim())?;
+
+  let breaking = captures.name("breaking").is_some()
+    || commit
+      .body
+      .lines()
+      .any(|line| line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:"));
+
+  Some(ConventionalCommit {
+    kind: captures["kind"].to_lowercase(),
+    scope: captures
+      .name("scope")
+      .map(|scope| scope.as_str().to_string()),
+    description: captures["description"].to_string(),
+    breaking,
+  })
+}
+
+/// Parses every commit, the ones not following the specification are ignored.
+pub fn parse_commits(commits: &[Commit]) -> Vec<ConventionalCommit> {
+  commits.iter().filter_map(parse_commit).collect()
+}
+
+/// The level of changes found in the commits.
+enum Level {
+  Patch,
+  Minor,
+  Major,
+}
+
+/// What the commits since the last release are made of.
+pub struct Analysis {
+  pub breaking: usize,
+  pub features: usize,
+  pub fixes: usize,
+  pub others: usize,
+}
+
+impl Analysis {
+  pub fn new(commits: &[Commit]) -> Self {
+    let conventional_commits = parse_commits(commits);
+
+    let mut analysis = Self {
+      breaking: 0,
+      features: 0,
+      fixes: 0,
+      // Commits not following the specification are counted as well.
+      others: commits.len() - conventional_commits.len(),
+    };
+
+    for commit in conventional_commits {
+      if commit.breaking {
+        analysis.breaking += 1;
+      }
+      else if commit.kind == "feat" {
+        analysis.features += 1;
+      }
+      else if commit.kind == "fix" {
+        analysis.fixes += 1;
+      }
+      else {
+        analysis.others += 1;
+      }
+    }
+
+    analysis
+  }
+
+  fn level(&self, version: &Version) -> Level {
+    if self.breaking > 0 {
+      // Before 1.0.0, anything may change at any time so
+      // breaking changes only bump the minor.
+      if version.major == 0 {
+        Level::Minor
+      }
+      else {
+        Level::Major
+      }
+    }
+    else if self.features > 0 {
+      Level::Minor
+    }
+    else {
+      Level::Patch
+    }
+  }
+
+  /// Recommends the bump to apply on the given version.
+  ///
+  /// When the version is a pre-release that already covers the changes,
+  /// e.g. `2.0.0-rc.1` with only fixes, we stay on its channel.
+  pub fn recommend_bump(&self, version: &Version) -> Bump {
+    let level = self.level(version);
+
+    let channel = match version.channel() {
+      Some(channel) => channel.to_string(),
+      None => {
+        return match level {
+          Level::Major => Bump::Major,
+          Level::Minor => Bump::Minor,
+          Level::Patch => Bump::Patch,
+        }
+      }
+    };
+
+    match level {
+      Level::Major if version.minor != 0 || version.patch != 0 => Bump::Premajor(channel),
+      Level::Minor if version.patch != 0 => Bump::Preminor(channel),
+      _ => Bump::Prerelease(channel),
+    }
+  }
+}
+
+impl fmt::Display for Analysis {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{} breaking change(s), {} feature(s), {} fix(es) and {} other commit(s)",
+      self.breaking, self.features, self.fixes, self.others
+    )
+  }
+}
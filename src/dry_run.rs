@@ -0,0 +1,13 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether we should only show what would happen, without touching
+/// the files, the repository or the remote.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+  ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+  ENABLED.load(Ordering::Relaxed)
+}
@@ -1,6 +1,7 @@
-use anyhow::Result;
+use crate::{dry_run, remote::Remote, tag::TagTemplate, version::Version};
+use anyhow::{Context, Result};
 use colored::Colorize;
-use std::process::{Command, Output};
+use std::process::{Command, ExitStatus, Output};
 
 pub fn git(args: &[&str]) -> Output {
   let log = format!("+> git {}", args.join(" "));
@@ -12,33 +13,99 @@ pub fn git(args: &[&str]) -> Output {
     .expect("failed to run git command, make sure git is installed on your machine")
 }
 
-// get every commit message between the two versions
-pub fn diff(old_version: &str, new_version: &str) -> String {
-  let references = format!("{old_version}..{new_version}");
+/// Same as [`git`] but for commands that modify the repository or the remote,
+/// in dry-run mode the command is only printed and considered successful.
+pub fn git_write(args: &[&str]) -> Output {
+  if !dry_run::is_enabled() {
+    return git(args);
+  }
 
-  let output = git(&["log", "--oneline", "--pretty=format:%s (%h)", &references]);
-  let output = String::from_utf8_lossy(&output.stdout).to_string();
+  let log = format!("+> git {} (dry-run, skipped)", args.join(" "));
+  println!("{}", log.bright_black());
 
-  let mut lines = output.lines().collect::<Vec<_>>();
+  Output {
+    status: ExitStatus::default(),
+    stdout: Vec::new(),
+    stderr: Vec::new(),
+  }
+}
 
-  // reverse the lines to get the oldest commit first
-  lines.reverse();
+/// Runs a command with [`git_write`] and fails with its output when it's unsuccessful.
+pub fn run(args: &[&str]) -> Result<()> {
+  let output = git_write(args);
 
-  // remove the last line because it's the version commit
-  lines.pop();
+  if !output.status.success() {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    anyhow::bail!(
+      "git {} failed, stdout: {stdout} / stderr: {stderr}",
+      args[0]
+    );
+  }
 
-  lines
-    .iter()
-    .map(|line| format!("* {line}"))
-    .collect::<Vec<_>>()
-    .join("\n")
+  Ok(())
+}
+
+pub struct Commit {
+  pub hash: String,
+  pub short_hash: String,
+  pub author: String,
+  pub subject: String,
+  pub body: String,
+}
+
+/// Get every commit made since the given tag, latest first.
+/// Without tag, every commit of the current branch is returned.
+/// With a `path`, only the commits touching it are returned.
+pub fn commits_since(tag: Option<&str>, path: Option<&str>) -> Vec<Commit> {
+  // Fields are separated by a unit separator and commits by a record separator,
+  // since commit bodies can contain pretty much anything else.
+  let format = "--pretty=format:%H%x1f%h%x1f%an%x1f%s%x1f%b%x1e";
+
+  let references = match tag {
+    Some(tag) => format!("{tag}..HEAD"),
+    None => "HEAD".to_string(),
+  };
+
+  let mut args = vec!["log", format, &references];
+  if let Some(path) = path {
+    args.extend(["--", path]);
+  }
+
+  let output = git(&args);
+  let output = String::from_utf8_lossy(&output.stdout).to_string();
+
+  output
+    .split('\x1e')
+    .filter_map(|record| {
+      let mut fields = record.trim_start_matches('\n').split('\x1f');
+
+      Some(Commit {
+        hash: fields.next()?.to_string(),
+        short_hash: fields.next()?.to_string(),
+        author: fields.next()?.to_string(),
+        subject: fields.next()?.to_string(),
+        body: fields.next()?.trim().to_string(),
+      })
+    })
+    .collect()
 }
 
-pub fn origin_url() -> String {
-  let output = git(&["remote", "get-url", "origin"]);
+pub fn remote_url(name: &str) -> Result<String> {
+  let output = git(&["remote", "get-url", name]);
+
+  if !output.status.success() {
+    anyhow::bail!("remote '{name}' doesn't exist in this repository");
+  }
+
   let url = String::from_utf8_lossy(&output.stdout);
+  Ok(url.trim().to_string())
+}
 
-  url.trim().to_string()
+/// Reads and parses the URL of the given remote.
+pub fn remote(name: &str) -> Result<Remote> {
+  let url = remote_url(name)?;
+  Remote::parse(&url).with_context(|| format!("couldn't parse the URL of remote '{name}'"))
 }
 
 pub fn branch_name() -> String {
@@ -58,13 +125,13 @@ pub fn is_repo_dirty() -> Result<bool> {
   Ok(!output.stdout.is_empty())
 }
 
-pub fn is_behind_upstream(branch_name: &str) -> Result<bool> {
-  let fetch = git(&["fetch"]);
+pub fn is_behind_upstream(remote: &str, branch_name: &str) -> Result<bool> {
+  let fetch = git(&["fetch", remote]);
   if !fetch.status.success() {
     return Err(anyhow::anyhow!("failed to fetch from remote"));
   }
 
-  let upstream = format!("origin/{branch_name}");
+  let upstream = format!("{remote}/{branch_name}");
   let output = git(&["rev-list", "--count", &format!("HEAD..{upstream}")]);
 
   if !output.status.success() {
@@ -88,3 +155,67 @@ pub fn tags() -> Vec<String> {
 
   output.lines().map(|line| line.into()).collect::<Vec<_>>()
 }
+
+/// Get the versions of every tag matching the template, ignoring the others.
+pub fn version_tags(template: &TagTemplate) -> Vec<Version> {
+  tags()
+    .iter()
+    .filter_map(|tag| template.parse(tag))
+    .collect()
+}
+
+/// Finds the tag of the release preceding the given version: the tag of
+/// this exact version when it exists, the closest lower one otherwise.
+pub fn previous_release_tag(template: &TagTemplate, version: &Version) -> Option<String> {
+  tags()
+    .into_iter()
+    .filter_map(|tag| Some((template.parse(&tag)?, tag)))
+    .filter(|(tag_version, _)| tag_version <= version)
+    .max_by(|(a, _), (b, _)| a.cmp(b))
+    .map(|(_, tag)| tag)
+}
+
+/// Writes the current index as a tree and returns its hash, to restore it later on.
+pub fn write_tree() -> Result<String> {
+  let output = git(&["write-tree"]);
+
+  if !output.status.success() {
+    anyhow::bail!("failed to save the index");
+  }
+
+  Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Hash of the object the tag points to, `None` when the tag doesn't exist.
+pub fn tag_object(tag: &str) -> Option<String> {
+  let output = git(&[
+    "rev-parse",
+    "--quiet",
+    "--verify",
+    &format!("refs/tags/{tag}"),
+  ]);
+
+  output
+    .status
+    .success()
+    .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Path of a file within the `.git` directory, works from worktrees as well.
+pub fn git_path(name: &str) -> Result<String> {
+  let output = git(&["rev-parse", "--git-path", name]);
+
+  if !output.status.success() {
+    anyhow::bail!("failed to find the git directory, is this a git repository?");
+  }
+
+  Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Files of the given path that changed since the tag.
+pub fn changed_files(tag: &str, path: &str) -> Vec<String> {
+  let output = git(&["diff", "--name-only", tag, "HEAD", "--", path]);
+  let output = String::from_utf8_lossy(&output.stdout);
+
+  output.lines().map(String::from).collect()
+}
@@ -1,10 +1,444 @@
-use crate::git;
+use crate::{dry_run, remote::Remote};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{env, fs, path::PathBuf, process::Command};
 
-pub fn open_create_release(release_body: String, tag_name: String, release_name: String) {
-  let origin_url = git::origin_url();
-  let release_body = urlencoding::encode(&release_body).into_owned();
+#[derive(Serialize, Deserialize)]
+pub struct Release {
+  pub tag_name: String,
+  pub name: String,
+  pub body: String,
+  pub prerelease: bool,
+  pub draft: bool,
+}
+
+/// Base URL of the REST API, can be overridden with `GITHUB_API_URL`
+/// - the same variable GitHub Actions sets - when it targets the host of the remote.
+fn api_url(remote: &Remote) -> String {
+  env::var("GITHUB_API_URL")
+    .ok()
+    .filter(|url| serves_host(url, &remote.host))
+    .unwrap_or_else(|| remote.api_url())
+    .trim_end_matches('/')
+    .to_string()
+}
+
+/// Host named by a URL, e.g. `ghe.example.com` for `https://ghe.example.com/api/v3`.
+fn url_host(url: &str) -> &str {
+  let authority = url.split_once("://").map_or(url, |(_, rest)| rest);
+  let authority = authority.split('/').next().unwrap_or(authority);
+
+  authority.split(':').next().unwrap_or(authority)
+}
+
+/// Whether the API at the URL belongs to the host, github.com is served by api.github.com.
+fn serves_host(url: &str, host: &str) -> bool {
+  let url_host = url_host(url);
+  url_host == host || (url_host == "api.github.com" && host == "github.com")
+}
+
+/// `GITHUB_TOKEN` and `GH_TOKEN` hold a token for github.com, or for the host named
+/// by `GH_HOST` or `GITHUB_API_URL`: they're never sent to another host.
+fn env_token(host: &str) -> Option<String> {
+  let gh_host = env::var("GH_HOST").is_ok_and(|value| url_host(&value) == host);
+  let api_host = env::var("GITHUB_API_URL").is_ok_and(|url| serves_host(&url, host));
+
+  if host != "github.com" && !gh_host && !api_host {
+    return None;
+  }
+
+  ["GITHUB_TOKEN", "GH_TOKEN"]
+    .iter()
+    .find_map(|name| env::var(name).ok().filter(|token| !token.is_empty()))
+}
+
+/// Finds the token of the host in the `hosts.yml` file of the GitHub CLI:
+/// every host is a top-level key, with its `oauth_token` indented below.
+fn hosts_token(hosts: &str, host: &str) -> Option<String> {
+  let mut current_host = None;
+
+  for line in hosts.lines() {
+    if !line.starts_with(char::is_whitespace) {
+      current_host = line.trim().strip_suffix(':');
+      continue;
+    }
+
+    if current_host != Some(host) {
+      continue;
+    }
+
+    if let Some(token) = line.trim().strip_prefix("oauth_token:") {
+      return Some(token.trim().to_string()).filter(|token| !token.is_empty());
+    }
+  }
+
+  None
+}
+
+/// Reads the token stored by the GitHub CLI in its configuration file.
+fn gh_config_token(host: &str) -> Option<String> {
+  let directory = match env::var("GH_CONFIG_DIR") {
+    Ok(directory) => PathBuf::from(directory),
+    Err(_) => PathBuf::from(env::var("HOME").ok()?).join(".config/gh"),
+  };
+
+  let hosts = fs::read_to_string(directory.join("hosts.yml")).ok()?;
+  hosts_token(&hosts, host)
+}
+
+/// Looks for a token of the host of the remote in `GITHUB_TOKEN` or `GH_TOKEN`,
+/// then in the GitHub CLI.
+fn token(remote: &Remote) -> Option<String> {
+  if let Some(token) = env_token(&remote.host) {
+    return Some(token);
+  }
+
+  // Recent versions of the GitHub CLI store the token in the system keyring,
+  // asking it directly works in every case.
+  if let Ok(output) = Command::new("gh")
+    .args(["auth", "token", "--hostname", &remote.host])
+    .output()
+  {
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !token.is_empty() {
+      return Some(token);
+    }
+  }
+
+  gh_config_token(&remote.host)
+}
+
+/// Creates the release through the REST API and returns its URL.
+/// https://docs.github.com/en/rest/releases/releases#create-a-release
+pub fn create_release(remote: &Remote, release: &Release, token: &str) -> Result<String> {
+  let url = format!(
+    "{}/repos/{}/{}/releases",
+    api_url(remote),
+    remote.owner,
+    remote.repo
+  );
+
+  let payload = serde_json::json!({
+    "tag_name": release.tag_name,
+    "name": release.name,
+    "body": release.body,
+    "draft": release.draft,
+    "prerelease": release.prerelease,
+  });
+
+  let log = format!("+> POST {url}");
+  println!("{}", log.bright_black());
+
+  let response = ureq::post(&url)
+    .set("Accept", "application/vnd.github+json")
+    .set("Authorization", &format!("Bearer {token}"))
+    .set("X-GitHub-Api-Version", "2022-11-28")
+    .set("User-Agent", "mentor")
+    .send_json(payload);
+
+  let response = match response {
+    Ok(response) => response,
+    Err(ureq::Error::Status(status, response)) => {
+      let error = response.into_string().unwrap_or_default();
+      anyhow::bail!("GitHub API responded with {status}: {error}");
+    }
+    Err(error) => return Err(error).context("failed to reach the GitHub API"),
+  };
+
+  let response: serde_json::Value = response
+    .into_json()
+    .context("GitHub API returned an invalid response")?;
+
+  let html_url = response
+    .get("html_url")
+    .and_then(|html_url| html_url.as_str())
+    .unwrap_or_default();
+
+  Ok(html_url.to_string())
+}
+
+/// Opens the browser on the page to create the release, prefilled with every field.
+pub fn open_create_release(remote: &Remote, release: &Release) {
+  let url = format!(
+    "{}/releases/new?tag={}&title={}&body={}&prerelease={}",
+    remote.web_url(),
+    urlencoding::encode(&release.tag_name),
+    urlencoding::encode(&release.name),
+    urlencoding::encode(&release.body),
+    release.prerelease
+  );
+
+  if dry_run::is_enabled() {
+    let log = format!("+> open {url} (dry-run, skipped)");
+    println!("{}", log.bright_black());
+    return;
+  }
+
+  // Headless machines can't open a browser, the URL can still be opened by hand.
+  if open::that(&url).is_err() {
+    println!("Open the following URL to create the release:\n{url}");
+  }
+}
+
+/// Creates the release through the API when a token is available,
+/// falls back on opening the browser otherwise.
+pub fn publish_release(remote: &Remote, release: &Release) {
+  let Some(token) = token(remote)
+  else {
+    open_create_release(remote, release);
+    return;
+  };
+
+  if dry_run::is_enabled() {
+    let kind = if release.draft {
+      "draft release"
+    }
+    else {
+      "release"
+    };
+    let log = format!(
+      "+> create {kind} {} through the GitHub API (dry-run, skipped)",
+      release.name
+    );
+    println!("{}", log.bright_black());
+    return;
+  }
+
+  match create_release(remote, release, &token) {
+    Ok(html_url) => println!("Release created at {html_url}"),
+    Err(error) => {
+      let message = format!("Couldn't create the release through the API, {error:#}");
+      println!("{}", message.yellow());
+
+      open_create_release(remote, release);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::Mutex,
+    thread::{self, JoinHandle},
+  };
+
+  /// Environment variables are shared by the whole process.
+  static ENV: Mutex<()> = Mutex::new(());
+
+  /// Answers a single request with the given status and body,
+  /// returns the base URL of the server and the head and body of the request.
+  fn serve(status: &'static str, body: &'static str) -> (String, JoinHandle<(String, String)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+      let (mut stream, _) = listener.accept().unwrap();
+      let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+      let mut head = String::new();
+      loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line.trim().is_empty() {
+          break;
+        }
+        head.push_str(&line);
+      }
+
+      let length = header(&head, "content-length").map_or(0, |length| length.parse().unwrap());
+      let mut request_body = vec![0; length];
+      reader.read_exact(&mut request_body).unwrap();
+
+      write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+      )
+      .unwrap();
+
+      (head, String::from_utf8(request_body).unwrap())
+    });
+
+    (url, handle)
+  }
+
+  fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().find_map(|line| {
+      let (key, value) = line.split_once(':')?;
+      key.eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+  }
+
+  fn remote(host: &str) -> Remote {
+    Remote {
+      host: host.into(),
+      owner: "owner".into(),
+      repo: "repo".into(),
+    }
+  }
+
+  fn release() -> Release {
+    Release {
+      tag_name: "v1.0.0".into(),
+      name: "v1.0.0".into(),
+      body: "### Features\n\n* something".into(),
+      prerelease: false,
+      draft: true,
+    }
+  }
+
+  fn create_release_on(url: &str) -> Result<String> {
+    let _env = ENV.lock().unwrap_or_else(|error| error.into_inner());
+
+    // The mock server runs on the host of the remote, as GitHub Enterprise would.
+    env::set_var("GITHUB_API_URL", format!("{url}/"));
+    let result = create_release(&remote("127.0.0.1"), &release(), "secret");
+    env::remove_var("GITHUB_API_URL");
+
+    result
+  }
+
+  #[test]
+  fn create_release_posts_the_release() {
+    let (url, request) = serve(
+      "201 Created",
+      r#"{"html_url": "https://github.com/owner/repo/releases/tag/v1.0.0"}"#,
+    );
+
+    let html_url = create_release_on(&url).unwrap();
+    assert_eq!(
+      html_url,
+      "https://github.com/owner/repo/releases/tag/v1.0.0"
+    );
+
+    let (head, body) = request.join().unwrap();
+    assert!(head.starts_with("POST /repos/owner/repo/releases HTTP/1.1\r\n"));
+    assert_eq!(header(&head, "authorization"), Some("Bearer secret"));
+    assert_eq!(header(&head, "accept"), Some("application/vnd.github+json"));
+    assert_eq!(header(&head, "x-github-api-version"), Some("2022-11-28"));
+
+    let payload: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+      payload,
+      serde_json::json!({
+        "tag_name": "v1.0.0",
+        "name": "v1.0.0",
+        "body": "### Features\n\n* something",
+        "draft": true,
+        "prerelease": false,
+      })
+    );
+  }
+
+  #[test]
+  fn create_release_fails_on_error_status() {
+    let (url, request) = serve(
+      "422 Unprocessable Entity",
+      r#"{"message": "Validation Failed"}"#,
+    );
+
+    let error = create_release_on(&url).unwrap_err().to_string();
+    request.join().unwrap();
+
+    assert!(error.contains("422"), "{error}");
+    assert!(error.contains("Validation Failed"), "{error}");
+  }
+
+  /// Runs `check` with only the given variables set among the ones read here,
+  /// and without any GitHub CLI configuration.
+  fn with_env(vars: &[(&str, &str)], check: impl FnOnce()) {
+    let _env = ENV.lock().unwrap_or_else(|error| error.into_inner());
+
+    let names = [
+      "GITHUB_API_URL",
+      "GITHUB_TOKEN",
+      "GH_TOKEN",
+      "GH_HOST",
+      "GH_CONFIG_DIR",
+    ];
+    let saved: Vec<_> = names
+      .iter()
+      .map(|name| (name, env::var(name).ok()))
+      .collect();
+
+    for name in names {
+      env::remove_var(name);
+    }
+    env::set_var("GH_CONFIG_DIR", env::temp_dir().join("mentor-no-gh-config"));
+    for (name, value) in vars {
+      env::set_var(name, value);
+    }
+
+    check();
+
+    for (name, value) in saved {
+      match value {
+        Some(value) => env::set_var(name, value),
+        None => env::remove_var(name),
+      }
+    }
+  }
+
+  #[test]
+  fn api_url_ignores_other_hosts() {
+    with_env(
+      &[("GITHUB_API_URL", "https://ghe.example.com/api/v3/")],
+      || {
+        assert_eq!(
+          api_url(&remote("ghe.example.com")),
+          "https://ghe.example.com/api/v3"
+        );
+        assert_eq!(api_url(&remote("github.com")), "https://api.github.com");
+        assert_eq!(
+          api_url(&remote("git.example.org")),
+          "https://git.example.org/api/v3"
+        );
+      },
+    );
+
+    with_env(&[("GITHUB_API_URL", "https://api.github.com")], || {
+      assert_eq!(api_url(&remote("github.com")), "https://api.github.com");
+      assert_eq!(
+        api_url(&remote("ghe.example.com")),
+        "https://ghe.example.com/api/v3"
+      );
+    });
+  }
+
+  #[test]
+  fn token_is_only_sent_to_its_host() {
+    let vars = [
+      ("GITHUB_API_URL", "https://ghe.example.com/api/v3"),
+      ("GITHUB_TOKEN", "secret"),
+    ];
+
+    with_env(&vars, || {
+      assert_eq!(token(&remote("ghe.example.com")).as_deref(), Some("secret"));
+      assert_eq!(token(&remote("github.com")).as_deref(), Some("secret"));
+      assert_eq!(token(&remote("git.example.org")), None);
+    });
+
+    with_env(&[("GITHUB_TOKEN", "secret")], || {
+      assert_eq!(token(&remote("ghe.example.com")), None);
+    });
+  }
 
-  let url = format!("{origin_url}/releases/new?tag={tag_name}&title={release_name}&body={release_body}&prerelease=false");
+  #[test]
+  fn hosts_token_matches_the_host() {
+    let hosts = "github.com:\n    oauth_token: gho_public\n    user: someone\nghe.example.com:\n    user: someone\n    oauth_token: gho_enterprise\n";
 
-  open::that(url).unwrap();
+    assert_eq!(
+      hosts_token(hosts, "github.com").as_deref(),
+      Some("gho_public")
+    );
+    assert_eq!(
+      hosts_token(hosts, "ghe.example.com").as_deref(),
+      Some("gho_enterprise")
+    );
+    assert_eq!(hosts_token(hosts, "gitlab.com"), None);
+  }
 }
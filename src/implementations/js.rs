@@ -1,45 +1,116 @@
-use crate::utils::{open_file, read_file, write_file};
-use anyhow::{Ok, Result};
+use super::Package;
+use crate::{
+  config::JsConfig,
+  utils::{file_exists, open_file, read_file, run_check, write_file},
+  version::{string_to_version, Version},
+};
+use anyhow::{Context, Ok, Result};
+use colored::Colorize;
 use regex::Regex;
 use std::{
   env::current_dir,
   fs::{read_dir, File},
   io,
+  ops::Range,
   path::Path,
-  process::Command,
 };
 
-fn detect_package_manager() -> Result<String> {
-  // As of right now, we only support bun and pnpm.
-  // Adding support for yarn and npm is not a priority right now...
-  let lockfiles = [
-    ("bun.lock", "bun"),
-    ("bun.lockb", "bun"),
-    ("pnpm-lock.yaml", "pnpm"),
-  ];
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PackageManager {
+  Npm,
+  /// Yarn 1.x
+  YarnClassic,
+  /// Yarn 2.x and above
+  YarnBerry,
+  Pnpm,
+  Bun,
+}
+
+impl PackageManager {
+  /// Reads the `packageManager` field of `package.json`, e.g. `yarn@4.1.0`.
+  /// https://nodejs.org/api/packages.html#packagemanager
+  fn from_field(field: &str) -> Option<Self> {
+    let (name, version) = field.split_once('@').unwrap_or((field, ""));
+
+    match name {
+      "npm" => Some(Self::Npm),
+      "yarn" if version.starts_with("1.") => Some(Self::YarnClassic),
+      "yarn" => Some(Self::YarnBerry),
+      "pnpm" => Some(Self::Pnpm),
+      "bun" => Some(Self::Bun),
+      _ => None,
+    }
+  }
+
+  /// Finds the package manager from a lockfile in the given directory.
+  fn from_lockfile(directory: &Path) -> Option<Self> {
+    let lockfiles = [
+      ("bun.lock", Self::Bun),
+      ("bun.lockb", Self::Bun),
+      ("pnpm-lock.yaml", Self::Pnpm),
+      ("yarn.lock", Self::YarnClassic),
+      ("package-lock.json", Self::Npm),
+      ("npm-shrinkwrap.json", Self::Npm),
+    ];
+
+    let (lockfile, package_manager) = lockfiles
+      .into_iter()
+      .find(|(lockfile, _)| directory.join(lockfile).exists())?;
+
+    // Lockfiles of Yarn Berry are YAML with a `__metadata` entry.
+    if package_manager == Self::YarnClassic {
+      let content = std::fs::read_to_string(directory.join(lockfile)).unwrap_or_default();
+
+      if content.contains("__metadata:") {
+        return Some(Self::YarnBerry);
+      }
+    }
+
+    Some(package_manager)
+  }
+
+  /// Program of the package manager, all of them run scripts with `<program> run <script>`.
+  fn program(&self) -> String {
+    let program = match self {
+      Self::Npm => "npm",
+      Self::YarnClassic | Self::YarnBerry => "yarn",
+      Self::Pnpm => "pnpm",
+      Self::Bun => "bun",
+    };
+
+    // Everything but bun is installed as a `.cmd` script on Windows.
+    if cfg!(windows) && *self != Self::Bun {
+      format!("{program}.cmd")
+    }
+    else {
+      program.to_string()
+    }
+  }
+}
+
+/// Reads the `packageManager` field of the `package.json` in the given directory.
+fn package_manager_field(directory: &Path) -> Option<String> {
+  let content = std::fs::read_to_string(directory.join(PACKAGE_JSON)).ok()?;
+  let json: serde_json::Value = serde_json::from_str(&content).ok()?;
 
-  // Start from current directory and walk up the parent directories.
+  json.get("packageManager")?.as_str().map(String::from)
+}
+
+fn detect_package_manager() -> Result<PackageManager> {
+  // Start from current directory and walk up the parent directories,
+  // to find the root of the workspace.
   let mut current_path = current_dir()?;
 
   loop {
-    for (lockfile, package_manager) in lockfiles.iter() {
-      let lockfile_path = current_path.join(lockfile);
-      if lockfile_path.exists() {
-        let package_manager = package_manager.to_string();
-
-        #[cfg(windows)]
-        let package_manager = format!(
-          "{package_manager}{}",
-          if package_manager == "pnpm" {
-            ".cmd"
-          }
-          else {
-            ""
-          }
-        );
+    // The `packageManager` field is explicit, it wins over the lockfiles.
+    if let Some(package_manager) =
+      package_manager_field(&current_path).and_then(|field| PackageManager::from_field(&field))
+    {
+      return Ok(package_manager);
+    }
 
-        return Ok(package_manager);
-      }
+    if let Some(package_manager) = PackageManager::from_lockfile(&current_path) {
+      return Ok(package_manager);
     }
 
     // Move to parent directory, or break if we've reached the root: found nothing...
@@ -101,93 +172,540 @@ fn has_test_files(dir: &Path) -> Result<bool> {
   check_directory(dir, &test_patterns)
 }
 
-pub fn run_checks() -> Result<()> {
-  let package_manager = detect_package_manager()?;
+/// Names of the scripts defined in `package.json`.
+fn scripts() -> Result<Vec<String>> {
+  let json: serde_json::Value = serde_json::from_reader(open_package_json()?)?;
 
-  // We're checking the code style of the project.
-  let output = Command::new(&package_manager).arg("eslint").output()?;
+  let scripts = json
+    .get("scripts")
+    .and_then(|scripts| scripts.as_object())
+    .map(|scripts| scripts.keys().cloned().collect())
+    .unwrap_or_default();
+
+  Ok(scripts)
+}
 
-  if !output.status.success() {
-    let error = String::from_utf8_lossy(&output.stdout);
-    return Err(anyhow::anyhow!(
-      "failed to check codestyle, see the following stack trace:\n\n{error}"
-    ));
+/// Runs the scripts of `package.json` used as checks: the configured ones,
+/// the `prerelease` script when it exists, or `lint`, `typecheck` and `test`.
+/// Missing scripts are skipped.
+pub fn run_checks(config: &JsConfig) -> Result<()> {
+  let package_manager = detect_package_manager()?;
+  let program = package_manager.program();
+  let scripts = scripts()?;
+
+  let checks = match &config.checks {
+    Some(checks) => checks.clone(),
+    // A dedicated script replaces the default ones.
+    None if scripts.iter().any(|script| script == "prerelease") => vec!["prerelease".into()],
+    None => vec!["lint".into(), "typecheck".into(), "test".into()],
+  };
+
+  for check in &checks {
+    if scripts.contains(check) {
+      run_check(
+        &format!("pass the '{check}' script"),
+        &program,
+        &["run", check],
+      )?;
+    }
+    // Bun has a built-in test runner, used when there's no `test` script.
+    else if check == "test"
+      && package_manager == PackageManager::Bun
+      && has_test_files(&current_dir()?)?
+    {
+      run_check("pass tests", &program, &["test"])?;
+    }
+    else {
+      let notice = format!("No '{check}' script in '{PACKAGE_JSON}', skipped.");
+      println!("{}", notice.yellow());
+    }
   }
 
-  // We're checking the types of the project.
-  let output = Command::new(&package_manager)
-    .arg("tsc")
-    .arg("--noEmit")
-    .output()?;
+  Ok(())
+}
 
-  if !output.status.success() {
-    let error = String::from_utf8_lossy(&output.stdout);
-    return Err(anyhow::anyhow!(
-      "failed to check types, see the following stack trace:\n\n{error}"
-    ));
+pub const PACKAGE_JSON: &str = "package.json";
+
+pub fn open_package_json() -> io::Result<File> {
+  open_file(PACKAGE_JSON)
+}
+
+/// A `package.json` file, the root one or the one of a workspace package.
+struct Manifest {
+  path: String,
+  /// Kept as text, so it's edited in place.
+  content: String,
+  json: serde_json::Value,
+}
+
+impl Manifest {
+  fn read(path: &str) -> Result<Self> {
+    let content =
+      read_file(&mut open_file(path).with_context(|| format!("failed to open '{path}'"))?)?;
+    let json =
+      serde_json::from_str(&content).with_context(|| format!("'{path}' is not valid JSON"))?;
+
+    Ok(Self {
+      path: path.to_string(),
+      content,
+      json,
+    })
   }
 
-  // We check the tests of a project, only if the package manager is bun
-  // since it has a built-in test runner.
-  if package_manager == "bun" {
-    let current_dir = current_dir()?;
+  fn name(&self) -> Option<&str> {
+    self.json.get("name")?.as_str()
+  }
 
-    if has_test_files(&current_dir)? {
-      // We're checking the tests of the project.
-      let output = Command::new(&package_manager).arg("test").output()?;
+  fn version(&self) -> Option<&str> {
+    self.json.get("version")?.as_str()
+  }
 
-      if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stdout);
-        return Err(anyhow::anyhow!(
-          "failed to pass tests, see the following stack trace:\n\n{error}"
-        ));
+  /// Replaces the string at the given path of keys, returns whether it was found.
+  fn set_string(&mut self, path: &[&str], value: &str) -> bool {
+    match find_string(&self.content, path) {
+      Some(range) => {
+        self.content.replace_range(range, value);
+        true
       }
+      None => false,
     }
   }
 
-  Ok(())
+  /// Updates the ranges of the dependencies on the released packages.
+  fn update_dependencies(&mut self, releases: &[(String, String)]) -> bool {
+    let mut changed = false;
+
+    for table in DEPENDENCY_TABLES {
+      for (name, version) in releases {
+        let Some(range) = self
+          .json
+          .get(table)
+          .and_then(|dependencies| dependencies.get(name))
+          .and_then(|range| range.as_str())
+          .and_then(|range| update_range(range, version))
+        else {
+          continue;
+        };
+
+        changed |= self.set_string(&[table, name], &range);
+      }
+    }
+
+    changed
+  }
+
+  fn write(self) -> Result<()> {
+    write_file(&self.path, self.content)
+  }
 }
 
-pub const PACKAGE_JSON: &str = "package.json";
+/// Tables of `package.json` that can depend on packages of the workspace.
+const DEPENDENCY_TABLES: [&str; 4] = [
+  "dependencies",
+  "devDependencies",
+  "peerDependencies",
+  "optionalDependencies",
+];
+
+/// Updates the range of a dependency to the released version, keeping its operator
+/// and the `workspace:` protocol. Ranges that aren't a single version,
+/// e.g. `workspace:*` or `1.x`, are kept as is.
+fn update_range(range: &str, version: &str) -> Option<String> {
+  let (protocol, range) = match range.strip_prefix("workspace:") {
+    Some(range) => ("workspace:", range),
+    None => ("", range),
+  };
+
+  let (operator, current) = range.split_at(range.find(|c: char| c.is_ascii_digit())?);
+
+  if !matches!(operator, "" | "^" | "~" | "=" | ">=") || string_to_version(current).is_err() {
+    return None;
+  }
 
-pub fn open_package_json() -> io::Result<File> {
-  open_file(PACKAGE_JSON)
+  Some(format!("{protocol}{operator}{version}"))
+}
+
+const PNPM_WORKSPACE_YAML: &str = "pnpm-workspace.yaml";
+
+/// Globs of the workspace packages, from `pnpm-workspace.yaml`
+/// or the `workspaces` field of `package.json`.
+fn workspace_patterns(root: &Manifest) -> Result<Vec<String>> {
+  if file_exists(PNPM_WORKSPACE_YAML) {
+    let content = read_file(&mut open_file(PNPM_WORKSPACE_YAML)?)?;
+
+    // Only the `packages` list is needed, no need for a complete YAML parser.
+    let patterns = content
+      .lines()
+      .skip_while(|line| !line.starts_with("packages:"))
+      .skip(1)
+      .take_while(|line| line.trim().is_empty() || line.starts_with(' ') || line.starts_with('-'))
+      .filter_map(|line| line.trim().strip_prefix('-'))
+      .map(|pattern| {
+        let pattern = pattern.split(" #").next().unwrap_or(pattern).trim();
+        pattern.trim_matches(['"', '\'']).to_string()
+      })
+      .collect();
+
+    return Ok(patterns);
+  }
+
+  // Yarn also accepts `{ "packages": [...] }`.
+  let workspaces = match root.json.get("workspaces") {
+    Some(serde_json::Value::Object(workspaces)) => workspaces.get("packages"),
+    workspaces => workspaces,
+  };
+
+  let patterns = workspaces
+    .and_then(|workspaces| workspaces.as_array())
+    .map(|patterns| {
+      patterns
+        .iter()
+        .filter_map(|pattern| pattern.as_str().map(String::from))
+        .collect()
+    })
+    .unwrap_or_default();
+
+  Ok(patterns)
+}
+
+/// The root `package.json` first, followed by the ones of the workspace packages.
+fn manifests() -> Result<Vec<Manifest>> {
+  let root = Manifest::read(PACKAGE_JSON)?;
+  let patterns = workspace_patterns(&root)?;
+
+  let (excluded, included): (Vec<_>, Vec<_>) = patterns
+    .iter()
+    .partition(|pattern| pattern.starts_with('!'));
+
+  let excluded = excluded
+    .iter()
+    .filter_map(|pattern| glob::Pattern::new(pattern.trim_start_matches('!')).ok())
+    .collect::<Vec<_>>();
+
+  let mut paths = Vec::new();
+
+  for pattern in included {
+    let directories =
+      glob::glob(pattern).with_context(|| format!("invalid workspace pattern '{pattern}'"))?;
+
+    for directory in directories.flatten() {
+      let manifest = directory.join(PACKAGE_JSON);
+
+      if directory == Path::new(".")
+        || directory
+          .components()
+          .any(|component| component.as_os_str() == "node_modules")
+        || excluded
+          .iter()
+          .any(|pattern| pattern.matches_path(&directory))
+        || !manifest.is_file()
+      {
+        continue;
+      }
+
+      let manifest = manifest.to_string_lossy().to_string();
+      if !paths.contains(&manifest) {
+        paths.push(manifest);
+      }
+    }
+  }
+
+  let mut manifests = paths
+    .iter()
+    .map(|path| Manifest::read(path))
+    .collect::<Result<Vec<_>>>()?;
+
+  manifests.insert(0, root);
+
+  Ok(manifests)
 }
 
-/// Reads the `package.json` file and parses it as JSON
-/// and returns the value of the `version` property as string.
-pub fn get_current_version() -> Result<String> {
-  let file = open_package_json()?;
-  let json: serde_json::Value = serde_json::from_reader(file)?;
+/// Every package of the workspace that has a name and a version.
+pub fn packages() -> Result<Vec<Package>> {
+  let manifests = manifests()?;
+
+  manifests[1..]
+    .iter()
+    .filter_map(|manifest| Some((manifest, manifest.name()?, manifest.version()?)))
+    .map(|(manifest, name, version)| {
+      let path = Path::new(&manifest.path).parent().unwrap_or(Path::new("."));
+
+      Ok(Package {
+        name: name.to_string(),
+        path: path.to_string_lossy().to_string(),
+        version: string_to_version(version)
+          .with_context(|| format!("'{}' has an invalid version", manifest.path))?,
+      })
+    })
+    .collect()
+}
+
+/// Reads the version of the root `package.json`, or the one shared
+/// by every package of the workspace when the root doesn't have one.
+fn current_version(manifests: &[Manifest]) -> Result<String> {
+  if let Some(version) = manifests[0].version() {
+    return Ok(version.to_string());
+  }
 
-  let version = json
-    .get("version")
-    .ok_or_else(|| anyhow::anyhow!("'package.json' is missing 'version' property"))?
-    .as_str()
-    .ok_or_else(|| anyhow::anyhow!("'version' should be a string"))?;
+  let mut versions = manifests.iter().filter_map(Manifest::version);
+
+  let Some(version) = versions.next()
+  else {
+    anyhow::bail!("'package.json' is missing 'version' property");
+  };
+
+  if versions.any(|other| other != version) {
+    anyhow::bail!(
+      "packages of the workspace have different versions, use 'versioning = \"independent\"'"
+    );
+  }
 
   Ok(version.to_string())
 }
 
-/// Edits the `package.json` file and updates the value of the `version` property.
-pub fn bump_version(version: &str) -> Result<()> {
-  let mut file = open_package_json()?;
+pub fn get_current_version() -> Result<Version> {
+  let version = current_version(&manifests()?)?;
+  string_to_version(&version).with_context(|| format!("'{PACKAGE_JSON}' has an invalid version"))
+}
 
-  let content = read_file(&mut file)?;
-  let mut content: serde_json::Value = serde_json::from_str(&content)?;
+/// Finds the string at the given path of keys, e.g. `["version"]`, in a JSON document
+/// and returns the range of its content, without the quotes.
+///
+/// Used to edit the document in place, keeping its formatting as is.
+fn find_string(content: &str, path: &[&str]) -> Option<Range<usize>> {
+  let bytes = content.as_bytes();
+
+  // Key of every container we're in, `None` for the root and the items of arrays.
+  let mut containers: Vec<Option<&str>> = Vec::new();
+  let mut key = None;
+  let mut index = 0;
+
+  while index < bytes.len() {
+    match bytes[index] {
+      b'{' | b'[' => containers.push(key.take()),
+      b'}' | b']' => {
+        containers.pop();
+      }
+      b',' => key = None,
+      b'"' => {
+        let start = index + 1;
+
+        // Find the closing quote, skipping the escaped characters.
+        let mut end = start;
+        while end < bytes.len() && bytes[end] != b'"' {
+          end += if bytes[end] == b'\\' { 2 } else { 1 };
+        }
 
-  let version_property = content
-    .get_mut("version")
-    .ok_or_else(|| anyhow::anyhow!("'package.json' is missing 'version' property"))?;
+        let string = content.get(start..end)?;
+        index = end;
 
-  *version_property = serde_json::Value::String(version.to_string());
+        let is_key = content.get(end + 1..)?.trim_start().starts_with(':');
 
-  let content = serde_json::to_string_pretty(&content)?;
+        if is_key {
+          key = Some(string);
+        }
+        else if let Some(key) = key.take() {
+          // Objects within arrays can't be reached with a path of keys.
+          let current = containers
+            .iter()
+            .skip(1)
+            .copied()
+            .chain([Some(key)])
+            .collect::<Option<Vec<_>>>();
+
+          if current.as_deref() == Some(path) {
+            return Some(start..end);
+          }
+        }
+      }
+      _ => (),
+    }
 
-  // Add a newline at the end of the file, to avoid issue with eslint !
-  let content = content + "\n";
+    index += 1;
+  }
 
-  write_file(&mut file, content)?;
+  None
+}
+
+/// Edits the `package.json` files and updates the value of the `version` property:
+/// the root one and the one of every package of the workspace released along.
+///
+/// Only the versions are replaced, the indentation, the order of the keys
+/// and the line endings of the files are kept.
+pub fn bump_version(version: &str) -> Result<()> {
+  let manifests = manifests()?;
+  let old_version = current_version(&manifests)?;
+
+  let releases = manifests
+    .iter()
+    .filter(|manifest| manifest.version() == Some(old_version.as_str()))
+    .filter_map(Manifest::name)
+    .map(|name| (name.to_string(), version.to_string()))
+    .collect::<Vec<_>>();
+
+  update_manifests(manifests, Some(version), &releases)
+}
+
+/// Bumps the given packages of the workspace, each one to its own version.
+pub fn bump_packages(releases: &[(String, String)]) -> Result<()> {
+  update_manifests(manifests()?, None, releases)
+}
+
+/// Sets the version of the root `package.json` - when it has one - and of the released
+/// packages, then updates the dependencies on them.
+fn update_manifests(
+  manifests: Vec<Manifest>,
+  root_version: Option<&str>,
+  releases: &[(String, String)],
+) -> Result<()> {
+  for (index, mut manifest) in manifests.into_iter().enumerate() {
+    let version = if index == 0 {
+      root_version
+    }
+    else {
+      manifest.name().and_then(|name| {
+        releases
+          .iter()
+          .find(|(released, _)| released == name)
+          .map(|(_, version)| version.as_str())
+      })
+    };
+
+    let mut changed = manifest.update_dependencies(releases);
+
+    if let Some(version) = version {
+      changed |= manifest.set_string(&["version"], version);
+    }
+
+    if changed {
+      manifest.write()?;
+    }
+  }
 
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn manifest(content: &str) -> Manifest {
+    Manifest {
+      path: PACKAGE_JSON.into(),
+      content: content.into(),
+      json: serde_json::from_str(content).unwrap(),
+    }
+  }
+
+  fn releases() -> Vec<(String, String)> {
+    vec![("@scope/core".into(), "1.1.0".into())]
+  }
+
+  /// Bumps the package and its dependency on `@scope/core`, like a release does.
+  fn bump(content: &str) -> String {
+    let mut manifest = manifest(content);
+
+    assert!(manifest.update_dependencies(&releases()));
+    assert!(manifest.set_string(&["version"], "1.1.0"));
+
+    manifest.content
+  }
+
+  #[test]
+  fn edits_keep_tabs() {
+    let content = "{\n\t\"name\": \"app\",\n\t\"version\": \"1.0.0\",\n\t\"dependencies\": {\n\t\t\"@scope/core\": \"^1.0.0\"\n\t}\n}";
+
+    assert_eq!(
+      bump(content),
+      "{\n\t\"name\": \"app\",\n\t\"version\": \"1.1.0\",\n\t\"dependencies\": {\n\t\t\"@scope/core\": \"^1.1.0\"\n\t}\n}"
+    );
+  }
+
+  #[test]
+  fn edits_keep_four_spaces() {
+    let content = "{\n    \"name\": \"app\",\n    \"version\": \"1.0.0\",\n    \"devDependencies\": {\n        \"@scope/core\": \"workspace:~1.0.0\"\n    }\n}\n";
+
+    assert_eq!(
+      bump(content),
+      "{\n    \"name\": \"app\",\n    \"version\": \"1.1.0\",\n    \"devDependencies\": {\n        \"@scope/core\": \"workspace:~1.1.0\"\n    }\n}\n"
+    );
+  }
+
+  #[test]
+  fn edits_keep_crlf() {
+    let content = "{\r\n  \"version\" : \"1.0.0\",\r\n  \"peerDependencies\": {\r\n    \"@scope/core\": \"1.0.0\"\r\n  }\r\n}\r\n";
+
+    assert_eq!(
+      bump(content),
+      "{\r\n  \"version\" : \"1.1.0\",\r\n  \"peerDependencies\": {\r\n    \"@scope/core\": \"1.1.0\"\r\n  }\r\n}\r\n"
+    );
+  }
+
+  #[test]
+  fn edits_skip_ranges_that_arent_a_version() {
+    let content = r#"{"version": "1.0.0", "dependencies": {"@scope/core": "workspace:*"}, "optionalDependencies": {"@scope/core": "1.x"}}"#;
+    let mut manifest = manifest(content);
+
+    assert!(!manifest.update_dependencies(&releases()));
+    assert_eq!(manifest.content, content);
+  }
+
+  #[test]
+  fn find_string_at_the_root() {
+    let content = r#"{"name": "app", "version": "1.0.0"}"#;
+    let range = find_string(content, &["version"]).unwrap();
+
+    assert_eq!(&content[range], "1.0.0");
+  }
+
+  #[test]
+  fn find_string_skips_nested_versions() {
+    let content = r#"{
+  "publishConfig": { "version": "0.0.1" },
+  "engines": { "node": ">=18" },
+  "version": "1.0.0"
+}"#;
+    let range = find_string(content, &["version"]).unwrap();
+
+    assert_eq!(&content[range.clone()], "1.0.0");
+    assert_eq!(range.start, content.rfind("1.0.0").unwrap());
+  }
+
+  #[test]
+  fn find_string_skips_objects_in_arrays() {
+    let content = r#"{
+  "contributors": [{ "name": "someone", "version": "0.0.1" }, ["version", "0.0.2"]],
+  "files": ["dist"],
+  "version": "1.0.0"
+}"#;
+    let range = find_string(content, &["version"]).unwrap();
+
+    assert_eq!(&content[range], "1.0.0");
+  }
+
+  #[test]
+  fn find_string_handles_escaped_quotes() {
+    let content = r#"{
+  "description": "the \"version\": \"0.0.1\" of \\",
+  "scripts": { "echo": "echo \"version\"" },
+  "version": "1.0.0"
+}"#;
+    let range = find_string(content, &["version"]).unwrap();
+
+    assert_eq!(&content[range], "1.0.0");
+  }
+
+  #[test]
+  fn find_string_in_nested_objects() {
+    let content = r#"{"dependencies": {"version": "2.0.0", "@scope/core": "^1.0.0"}}"#;
+
+    let range = find_string(content, &["dependencies", "@scope/core"]).unwrap();
+    assert_eq!(&content[range], "^1.0.0");
+
+    assert_eq!(find_string(content, &["version"]), None);
+    assert_eq!(
+      find_string(content, &["devDependencies", "@scope/core"]),
+      None
+    );
+  }
+}
@@ -1,17 +1,45 @@
-use crate::utils::{find_between, open_file, open_readme, read_file, write_file};
-use anyhow::Result;
+use crate::{
+  config::KotlinConfig,
+  utils::{
+    file_exists, find_between, open_file, open_readme, program_exists, read_file, run_check,
+    write_file, README_MD,
+  },
+  version::{string_to_version, Version},
+};
+use anyhow::{Context, Result};
 use std::{fs::File, io};
 
-pub const BUILD_GRADLE_KTS: &str = "library/build.gradle.kts";
+#[cfg(not(windows))]
+const GRADLE_WRAPPER: &str = "./gradlew";
+#[cfg(windows)]
+const GRADLE_WRAPPER: &str = "gradlew.bat";
+
+/// Runs `gradle check` through the Gradle wrapper of the project,
+/// or through the installed Gradle when the project doesn't have one.
+pub fn run_checks() -> Result<()> {
+  let gradle = if file_exists(GRADLE_WRAPPER) {
+    GRADLE_WRAPPER
+  }
+  else if program_exists("gradle") {
+    "gradle"
+  }
+  else {
+    anyhow::bail!(
+      "neither the Gradle wrapper nor Gradle were found to run the checks, add one or disable 'steps.checks'"
+    );
+  };
+
+  run_check("pass checks", gradle, &["check"])
+}
 
-pub fn open_build_gradle_kts() -> io::Result<File> {
-  open_file(BUILD_GRADLE_KTS)
+pub fn open_build_gradle_kts(config: &KotlinConfig) -> io::Result<File> {
+  open_file(&config.build_file)
 }
 
-/// Reads the `library/build.gradle.kts` file and parses it as KTS
-/// and returns the value of the `version` property as string.
-pub fn get_current_version() -> Result<String> {
-  let content = read_file(&mut open_build_gradle_kts()?)?;
+/// Reads the build file - `library/build.gradle.kts` by default - and parses it as KTS
+/// and returns the value of the `version` property.
+pub fn get_current_version(config: &KotlinConfig) -> Result<Version> {
+  let content = read_file(&mut open_build_gradle_kts(config)?)?;
 
   // Find the `version = "x.y.z"` line.
   let version_line = content
@@ -24,12 +52,16 @@ pub fn get_current_version() -> Result<String> {
     .nth(1)
     .ok_or_else(|| anyhow::anyhow!("'build.gradle.kts' is missing 'version' variable."))?;
 
-  Ok(version.to_string())
+  string_to_version(version)
+    .with_context(|| format!("'{}' has an invalid version", config.build_file))
 }
 
-fn bump_build_gradle_kts(old_version: &str, new_version: &str) -> Result<()> {
-  let mut file = open_build_gradle_kts()?;
-  let content = read_file(&mut file)?;
+fn bump_build_gradle_kts(
+  config: &KotlinConfig,
+  old_version: &str,
+  new_version: &str,
+) -> Result<()> {
+  let content = read_file(&mut open_build_gradle_kts(config)?)?;
 
   let from = format!("version = \"{old_version}\"");
   let to = format!("version = \"{new_version}\"");
@@ -38,14 +70,17 @@ fn bump_build_gradle_kts(old_version: &str, new_version: &str) -> Result<()> {
   // located at the very first lines of the file.
   let content = content.replacen(&from, &to, 1);
 
-  write_file(&mut file, content)?;
+  write_file(&config.build_file, content)?;
 
   Ok(())
 }
 
-fn bump_readme(old_version: &str, new_version: &str) -> Result<()> {
-  let mut file = open_readme()?;
-  let content = read_file(&mut file)?;
+fn bump_readme(config: &KotlinConfig, old_version: &str, new_version: &str) -> Result<()> {
+  if !file_exists(README_MD) {
+    return Ok(());
+  }
+
+  let content = read_file(&mut open_readme()?)?;
   let artifact_id = find_between(&content, "<artifactId>", "</artifactId>");
 
   // replace for maven section
@@ -53,26 +88,28 @@ fn bump_readme(old_version: &str, new_version: &str) -> Result<()> {
   let to = format!("<version>{new_version}</version>");
   let content = content.replace(&from, &to);
 
+  let group = &config.group;
+
   // replace for gradle (kotlin) section
-  let from = format!("implementation(\"ink.literate:{artifact_id}:{old_version}\")");
-  let to = format!("implementation(\"ink.literate:{artifact_id}:{new_version}\")");
+  let from = format!("implementation(\"{group}:{artifact_id}:{old_version}\")");
+  let to = format!("implementation(\"{group}:{artifact_id}:{new_version}\")");
   let content = content.replace(&from, &to);
 
   // replace for gradle section
-  let from = format!("implementation 'ink.literate:{artifact_id}:{old_version}'");
-  let to = format!("implementation 'ink.literate:{artifact_id}:{new_version}'");
+  let from = format!("implementation '{group}:{artifact_id}:{old_version}'");
+  let to = format!("implementation '{group}:{artifact_id}:{new_version}'");
   let content = content.replace(&from, &to);
 
-  write_file(&mut file, content)?;
+  write_file(README_MD, content)?;
 
   Ok(())
 }
 
-pub fn bump_version(new_version: &str) -> Result<()> {
-  let old_version = get_current_version()?;
+pub fn bump_version(config: &KotlinConfig, new_version: &str) -> Result<()> {
+  let old_version = get_current_version(config)?.to_string();
 
-  bump_build_gradle_kts(&old_version, new_version)?;
-  bump_readme(&old_version, new_version)?;
+  bump_build_gradle_kts(config, &old_version, new_version)?;
+  bump_readme(config, &old_version, new_version)?;
 
   Ok(())
 }
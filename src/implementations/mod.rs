@@ -1,4 +1,15 @@
 pub mod js;
 pub mod kotlin;
+pub mod python;
 pub mod rust;
 pub mod swift;
+
+use crate::version::Version;
+
+/// A package of a workspace, released on its own with independent versioning.
+pub struct Package {
+  pub name: String,
+  /// Directory of the package, relative to the root of the repository.
+  pub path: String,
+  pub version: Version,
+}
@@ -0,0 +1,450 @@
+use crate::{
+  utils::{file_exists, open_file, program_exists, read_file, run_check, write_file},
+  version::{string_to_version, Identifier, Version},
+};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use regex::Regex;
+use std::{fs, ops::Range};
+use toml_edit::{DocumentMut, Item};
+
+pub const PYPROJECT_TOML: &str = "pyproject.toml";
+pub const SETUP_CFG: &str = "setup.cfg";
+
+const PROJECT_VERSION: &[&str] = &["project", "version"];
+const POETRY_VERSION: &[&str] = &["tool", "poetry", "version"];
+const SETUPTOOLS_ATTR: &[&str] = &["tool", "setuptools", "dynamic", "version", "attr"];
+const HATCH_PATH: &[&str] = &["tool", "hatch", "version", "path"];
+
+/// Runs `ruff check` and `pytest`, each one only when it's installed.
+pub fn run_checks() -> Result<()> {
+  let checks: [(&str, &[&str], &str); 2] = [
+    ("ruff", &["check"], "pass the lints"),
+    ("pytest", &[], "pass tests"),
+  ];
+
+  for (program, args, description) in checks {
+    if program_exists(program) {
+      run_check(description, program, args)?;
+    }
+    else {
+      let notice = format!("'{program}' isn't installed, skipped.");
+      println!("{}", notice.yellow());
+    }
+  }
+
+  Ok(())
+}
+
+/// Where the version of the project is written.
+enum Location {
+  /// A `version` property of `pyproject.toml`, in `[project]` or `[tool.poetry]`.
+  Pyproject(&'static [&'static str]),
+  /// The `version` of the `[metadata]` section of `setup.cfg`.
+  SetupCfg,
+  /// A variable of a module, usually `__version__` in `__init__.py`,
+  /// when the version is dynamic.
+  Module { path: String, variable: String },
+}
+
+impl Location {
+  fn path(&self) -> &str {
+    match self {
+      Self::Pyproject(_) => PYPROJECT_TOML,
+      Self::SetupCfg => SETUP_CFG,
+      Self::Module { path, .. } => path,
+    }
+  }
+}
+
+fn read_pyproject() -> Result<DocumentMut> {
+  let content = read_file(&mut open_file(PYPROJECT_TOML)?)?;
+
+  content
+    .parse()
+    .with_context(|| format!("'{PYPROJECT_TOML}' is not valid TOML"))
+}
+
+fn get_str<'a>(document: &'a DocumentMut, keys: &[&str]) -> Option<&'a str> {
+  keys
+    .iter()
+    .try_fold(document.as_item(), |item, key| item.get(key))?
+    .as_str()
+}
+
+/// Whether the repository holds a Python project: `pyproject.toml` and `setup.cfg`
+/// are also used to configure tools such as ruff or flake8 in other projects.
+pub fn is_project() -> bool {
+  let has_pyproject = read_pyproject().is_ok_and(|pyproject| {
+    pyproject.contains_key("project")
+      || pyproject
+        .get("tool")
+        .is_some_and(|tool| tool.get("poetry").is_some())
+  });
+
+  has_pyproject
+    || fs::read_to_string(SETUP_CFG).is_ok_and(|content| setup_cfg_version(&content).is_some())
+}
+
+fn is_dynamic(pyproject: &DocumentMut) -> bool {
+  pyproject
+    .get("project")
+    .and_then(|project| project.get("dynamic"))
+    .and_then(Item::as_array)
+    .is_some_and(|dynamic| {
+      dynamic
+        .iter()
+        .any(|field| field.as_str() == Some("version"))
+    })
+}
+
+/// Whether the build backend computes the version from somewhere we can't write to,
+/// e.g. git tags with setuptools-scm or hatch-vcs, or `Cargo.toml` with maturin.
+fn is_set_by_backend(pyproject: &DocumentMut) -> bool {
+  let is_flit = get_str(pyproject, &["build-system", "build-backend"])
+    .is_some_and(|backend| backend.starts_with("flit"));
+
+  is_dynamic(pyproject)
+    && get_str(pyproject, SETUPTOOLS_ATTR).is_none()
+    && get_str(pyproject, HATCH_PATH).is_none()
+    && !is_flit
+}
+
+/// Looks for the version in `pyproject.toml` first, then in `setup.cfg`,
+/// `None` when it's set by the build backend.
+fn locate() -> Result<Option<Location>> {
+  if file_exists(PYPROJECT_TOML) {
+    let pyproject = read_pyproject()?;
+
+    if get_str(&pyproject, PROJECT_VERSION).is_some() {
+      return Ok(Some(Location::Pyproject(PROJECT_VERSION)));
+    }
+
+    if is_set_by_backend(&pyproject) {
+      return Ok(None);
+    }
+
+    if is_dynamic(&pyproject) {
+      return dynamic_location(&pyproject).map(Some);
+    }
+
+    if get_str(&pyproject, POETRY_VERSION).is_some() {
+      return Ok(Some(Location::Pyproject(POETRY_VERSION)));
+    }
+  }
+
+  if file_exists(SETUP_CFG) {
+    let content = read_file(&mut open_file(SETUP_CFG)?)?;
+
+    if let Some(range) = setup_cfg_version(&content) {
+      // e.g. `version = attr: package.__version__`
+      return match content[range].strip_prefix("attr:") {
+        Some(attr) => module_location(attr.trim()).map(Some),
+        None => Ok(Some(Location::SetupCfg)),
+      };
+    }
+  }
+
+  anyhow::bail!("couldn't find the version in '{PYPROJECT_TOML}' nor in '{SETUP_CFG}'")
+}
+
+/// Finds the module a dynamic version is read from: the `attr` of setuptools,
+/// the `path` of Hatch, or the `__init__.py` of the package as Flit does.
+fn dynamic_location(pyproject: &DocumentMut) -> Result<Location> {
+  if let Some(attr) = get_str(pyproject, SETUPTOOLS_ATTR) {
+    return module_location(attr);
+  }
+
+  if let Some(path) = get_str(pyproject, HATCH_PATH) {
+    return Ok(Location::Module {
+      path: path.to_string(),
+      variable: "__version__".into(),
+    });
+  }
+
+  let name = get_str(pyproject, &["project", "name"])
+    .with_context(|| format!("'{PYPROJECT_TOML}' is missing 'project.name' property"))?;
+
+  // Modules use underscores where the names of the projects use dashes.
+  let module = name.replace(['-', '.'], "_").to_lowercase();
+  module_location(&format!("{module}.__version__"))
+}
+
+/// Resolves `package.module.__version__` to the file of the module,
+/// at the root of the repository or within `src`.
+fn module_location(attr: &str) -> Result<Location> {
+  let Some((module, variable)) = attr.rsplit_once('.')
+  else {
+    anyhow::bail!("'{attr}' doesn't point to the variable of a module");
+  };
+
+  let module = module.replace('.', "/");
+
+  let path = ["", "src/"]
+    .into_iter()
+    .flat_map(|dir| {
+      [
+        format!("{dir}{module}/__init__.py"),
+        format!("{dir}{module}.py"),
+      ]
+    })
+    .find(|path| file_exists(path))
+    .with_context(|| format!("couldn't find the module holding the version, '{attr}'"))?;
+
+  Ok(Location::Module {
+    path,
+    variable: variable.to_string(),
+  })
+}
+
+/// Finds the value of `version` in the `[metadata]` section of `setup.cfg`.
+fn setup_cfg_version(content: &str) -> Option<Range<usize>> {
+  let mut in_metadata = false;
+  let mut offset = 0;
+
+  for line in content.split_inclusive('\n') {
+    let start = offset;
+    offset += line.len();
+
+    let trimmed = line.trim();
+    if trimmed.starts_with('[') {
+      in_metadata = trimmed == "[metadata]";
+      continue;
+    }
+
+    // Indented lines continue the value of the previous option.
+    if !in_metadata || line.starts_with(char::is_whitespace) {
+      continue;
+    }
+
+    let Some((key, value)) = line.split_once(['=', ':'])
+    else {
+      continue;
+    };
+
+    if key.trim() == "version" {
+      let value_start = start + key.len() + 1 + (value.len() - value.trim_start().len());
+      return Some(value_start..value_start + value.trim().len());
+    }
+  }
+
+  None
+}
+
+/// Finds the string assigned to the variable, e.g. `__version__ = "1.2.3"`.
+fn module_version(content: &str, variable: &str) -> Option<Range<usize>> {
+  let assignment = format!(
+    r#"(?m)^{}\s*(?::\s*\w+\s*)?=\s*["']([^"']*)["']"#,
+    regex::escape(variable)
+  );
+
+  Regex::new(&assignment)
+    .ok()?
+    .captures(content)?
+    .get(1)
+    .map(|version| version.range())
+}
+
+/// Range of the version within a `setup.cfg` or a module.
+fn find_version(location: &Location, content: &str) -> Option<Range<usize>> {
+  match location {
+    Location::Pyproject(_) => None,
+    Location::SetupCfg => setup_cfg_version(content),
+    Location::Module { variable, .. } => module_version(content, variable),
+  }
+}
+
+/// Reads a PEP 440 version as SemVer: missing parts are zeros and pre-releases
+/// use the channels of SemVer, e.g. `1.0rc1` is `1.0.0-rc.1`.
+/// Post-releases, dev-releases and epochs have no SemVer equivalent.
+fn from_pep440(version: &str) -> Option<Version> {
+  let pattern = Regex::new(
+    r"(?i)^v?(\d+)(?:\.(\d+))?(?:\.(\d+))?(?:[-_.]?(a|alpha|b|beta|c|rc|pre|preview)[-_.]?(\d*))?$",
+  )
+  .unwrap();
+
+  let captures = pattern.captures(version.trim())?;
+  let number = |index| {
+    captures
+      .get(index)
+      .map_or(Some(0), |number: regex::Match| number.as_str().parse().ok())
+  };
+
+  let mut semver = Version::new(number(1)?, number(2)?, number(3)?);
+
+  if let Some(channel) = captures.get(4) {
+    let channel = match channel.as_str().to_lowercase().as_str() {
+      "a" | "alpha" => "alpha",
+      "b" | "beta" => "beta",
+      _ => "rc",
+    };
+
+    // `1.0rc` is the same as `1.0rc0`.
+    let counter = captures
+      .get(5)
+      .filter(|counter| !counter.is_empty())
+      .map_or(Some(0), |counter| counter.as_str().parse().ok())?;
+
+    semver.pre = vec![
+      Identifier::AlphaNumeric(channel.into()),
+      Identifier::Numeric(counter),
+    ];
+  }
+
+  Some(semver)
+}
+
+/// Writes the version the way PEP 440 normalizes it, e.g. `1.0.0-rc.1` is `1.0.0rc1`.
+fn to_pep440(version: &Version) -> Result<String> {
+  let release = format!("{}.{}.{}", version.major, version.minor, version.patch);
+
+  let pre = match version.pre.as_slice() {
+    [] => Some(String::new()),
+    [Identifier::AlphaNumeric(channel), Identifier::Numeric(counter)] => match channel.as_str() {
+      "alpha" => Some(format!("a{counter}")),
+      "beta" => Some(format!("b{counter}")),
+      "rc" => Some(format!("rc{counter}")),
+      _ => None,
+    },
+    _ => None,
+  };
+
+  match pre {
+    Some(pre) if version.build.is_empty() => Ok(format!("{release}{pre}")),
+    _ => anyhow::bail!(
+      "{version} can't be written as a Python version, only the 'alpha', 'beta' and 'rc' pre-releases can"
+    ),
+  }
+}
+
+/// Reads the version of the project, `None` when it's set by the build backend.
+pub fn get_current_version() -> Result<Option<Version>> {
+  let Some(location) = locate()?
+  else {
+    return Ok(None);
+  };
+
+  let path = location.path();
+
+  let version = match &location {
+    Location::Pyproject(keys) => get_str(&read_pyproject()?, keys).map(String::from),
+    _ => {
+      let content = read_file(&mut open_file(path)?)?;
+      find_version(&location, &content).map(|range| content[range].to_string())
+    }
+  };
+
+  let version = version.with_context(|| format!("'{path}' is missing the version"))?;
+
+  let version = from_pep440(&version).with_context(|| {
+    format!(
+      "'{path}' has version '{version}', only versions such as '1.2', '1.2.3' or '1.2.3rc1' are supported: post-releases, dev-releases and epochs aren't"
+    )
+  })?;
+
+  Ok(Some(version))
+}
+
+/// Replaces the version where it was found, the rest of the file is kept as is.
+/// Nothing is written when the version is set by the build backend.
+pub fn bump_version(version: &str) -> Result<()> {
+  let Some(location) = locate()?
+  else {
+    return Ok(());
+  };
+
+  let version = &to_pep440(&string_to_version(version)?)?;
+  let path = location.path();
+
+  match &location {
+    Location::Pyproject(keys) => {
+      let mut pyproject = read_pyproject()?;
+
+      let value = keys
+        .iter()
+        .try_fold(pyproject.as_item_mut(), |item, key| {
+          item.as_table_like_mut()?.get_mut(key)
+        })
+        .and_then(Item::as_value_mut)
+        .with_context(|| format!("'{path}' is missing the version"))?;
+
+      let decor = value.decor().clone();
+      *value = version.into();
+      *value.decor_mut() = decor;
+
+      write_file(path, pyproject.to_string())
+    }
+    _ => {
+      let mut content = read_file(&mut open_file(path)?)?;
+      let range = find_version(&location, &content)
+        .with_context(|| format!("'{path}' is missing the version"))?;
+
+      content.replace_range(range, version);
+      write_file(path, content)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn semver(version: &str) -> Option<String> {
+    from_pep440(version).map(|version| version.to_string())
+  }
+
+  #[test]
+  fn reads_pep440_versions() {
+    assert_eq!(semver("0.1").as_deref(), Some("0.1.0"));
+    assert_eq!(semver("1.2.3").as_deref(), Some("1.2.3"));
+    assert_eq!(semver("v1.2.3").as_deref(), Some("1.2.3"));
+    assert_eq!(semver("1.0.0rc1").as_deref(), Some("1.0.0-rc.1"));
+    assert_eq!(semver("1.0c2").as_deref(), Some("1.0.0-rc.2"));
+    assert_eq!(semver("1.0.0a1").as_deref(), Some("1.0.0-alpha.1"));
+    assert_eq!(semver("1.0.0-beta.2").as_deref(), Some("1.0.0-beta.2"));
+    assert_eq!(semver("1.0.0b").as_deref(), Some("1.0.0-beta.0"));
+  }
+
+  #[test]
+  fn rejects_versions_without_semver_equivalent() {
+    assert_eq!(semver("1.0.0.post1"), None);
+    assert_eq!(semver("1.0.0.dev3"), None);
+    assert_eq!(semver("1!1.0.0"), None);
+    assert_eq!(semver("1.0.0.1"), None);
+  }
+
+  #[test]
+  fn detects_versions_set_by_the_backend() {
+    let pyproject = |content: &str| content.parse::<DocumentMut>().unwrap();
+    let dynamic = "[project]\nname = \"app\"\ndynamic = [\"version\"]\n";
+
+    for backend in [
+      "[tool.setuptools_scm]\n",
+      "[build-system]\nbuild-backend = \"hatchling.build\"\n\n[tool.hatch.version]\nsource = \"vcs\"\n",
+      "[build-system]\nbuild-backend = \"maturin\"\n",
+    ] {
+      assert!(is_set_by_backend(&pyproject(&format!("{dynamic}\n{backend}"))), "{backend}");
+    }
+
+    for content in [
+      "[project]\nname = \"app\"\nversion = \"1.0.0\"\n".to_string(),
+      format!("{dynamic}\n[tool.setuptools.dynamic]\nversion = {{ attr = \"app.__version__\" }}\n"),
+      format!("{dynamic}\n[tool.hatch.version]\npath = \"src/app/__about__.py\"\n"),
+      format!("{dynamic}\n[build-system]\nbuild-backend = \"flit_core.buildapi\"\n"),
+    ] {
+      assert!(!is_set_by_backend(&pyproject(&content)), "{content}");
+    }
+  }
+
+  #[test]
+  fn writes_pep440_versions() {
+    let pep440 = |version: &str| to_pep440(&string_to_version(version).unwrap()).ok();
+
+    assert_eq!(pep440("1.2.3").as_deref(), Some("1.2.3"));
+    assert_eq!(pep440("1.0.0-alpha.1").as_deref(), Some("1.0.0a1"));
+    assert_eq!(pep440("1.0.0-beta.0").as_deref(), Some("1.0.0b0"));
+    assert_eq!(pep440("1.0.0-rc.2").as_deref(), Some("1.0.0rc2"));
+    assert_eq!(pep440("1.0.0-next.1"), None);
+    assert_eq!(pep440("1.0.0+build.1"), None);
+  }
+}
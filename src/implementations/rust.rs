@@ -1,52 +1,704 @@
-use crate::utils::{open_file, read_file, write_file};
-use anyhow::Result;
-use std::{fs::File, io, process::Command};
+use super::Package;
+use crate::{
+  config::{RustCheck, RustConfig},
+  utils::{file_exists, open_file, read_file, run_check, write_file},
+  version::{string_to_version, Version},
+};
+use anyhow::{Context, Result};
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, TableLike};
 
 pub const CARGO_TOML: &str = "Cargo.toml";
+pub const CARGO_LOCK: &str = "Cargo.lock";
 
-pub fn open_cargo_toml() -> io::Result<File> {
-  open_file(CARGO_TOML)
+/// Tables that can declare dependencies, at the root of a manifest
+/// or within a `[target.'cfg(...)']` table.
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Runs the checks enabled in the configuration, stops at the first failing one.
+pub fn run_checks(config: &RustConfig) -> Result<()> {
+  for check in &config.checks {
+    match check {
+      RustCheck::Fmt => run_check("check codestyle", "cargo", &["fmt", "--check"])?,
+      RustCheck::Clippy => run_check(
+        "pass the lints",
+        "cargo",
+        &["clippy", "--", "-D", "warnings"],
+      )?,
+      RustCheck::Test => run_check("pass tests", "cargo", &["test"])?,
+    }
+  }
+
+  Ok(())
+}
+
+/// A package released along, with its version before and after the release.
+struct Released {
+  name: String,
+  old_version: String,
+  new_version: String,
+}
+
+/// A `Cargo.toml` file, the root one or the one of a workspace member.
+///
+/// Edited as a document so only the versions are changed,
+/// comments and formatting of the file are kept as is.
+struct Manifest {
+  path: String,
+  content: DocumentMut,
+}
+
+impl Manifest {
+  fn read(path: &str) -> Result<Self> {
+    let content =
+      read_file(&mut open_file(path).with_context(|| format!("failed to open '{path}'"))?)?;
+    let content = content
+      .parse()
+      .with_context(|| format!("'{path}' is not valid TOML"))?;
+
+    Ok(Self {
+      path: path.to_string(),
+      content,
+    })
+  }
+
+  fn write(&self) -> Result<()> {
+    write_file(&self.path, self.content.to_string())
+  }
+
+  fn package(&self) -> Option<&Item> {
+    self.content.get("package")
+  }
+
+  fn name(&self) -> Option<&str> {
+    self.package()?.get("name")?.as_str()
+  }
+
+  /// Version of the package itself, `None` when it's inherited or missing.
+  fn version(&self) -> Option<&str> {
+    self.package()?.get("version")?.as_str()
+  }
+
+  /// Whether the package uses `version.workspace = true`.
+  fn inherits_version(&self) -> bool {
+    self
+      .package()
+      .and_then(|package| package.get("version"))
+      .and_then(|version| version.get("workspace"))
+      .and_then(|workspace| workspace.as_bool())
+      .unwrap_or(false)
+  }
+
+  /// The `[workspace.package].version` shared by the members.
+  fn workspace_version(&self) -> Option<&str> {
+    self
+      .content
+      .get("workspace")?
+      .get("package")?
+      .get("version")?
+      .as_str()
+  }
+
+  /// Paths of the `Cargo.toml` of every member of the workspace, without the root one.
+  fn members(&self) -> Result<Vec<String>> {
+    let Some(workspace) = self.content.get("workspace")
+    else {
+      return Ok(Vec::new());
+    };
+
+    let strings = |key: &str| {
+      workspace
+        .get(key)
+        .and_then(Item::as_array)
+        .map(|values| values.iter().filter_map(|value| value.as_str()).collect())
+        .unwrap_or_else(Vec::new)
+    };
+
+    let excluded = strings("exclude")
+      .into_iter()
+      .map(|path| Path::new(path).to_path_buf())
+      .collect::<Vec<_>>();
+
+    let mut members = Vec::new();
+
+    // Members can be glob patterns, e.g. `crates/*`.
+    for pattern in strings("members") {
+      let paths = glob::glob(pattern)
+        .with_context(|| format!("invalid workspace member '{pattern}' in '{CARGO_TOML}'"))?;
+
+      for path in paths.flatten() {
+        let manifest = path.join(CARGO_TOML);
+
+        if path == Path::new(".") || excluded.contains(&path) || !manifest.is_file() {
+          continue;
+        }
+
+        let manifest = manifest.to_string_lossy().to_string();
+        if !members.contains(&manifest) {
+          members.push(manifest);
+        }
+      }
+    }
+
+    Ok(members)
+  }
+
+  /// Rewrites the version requirement of every `path` dependency on the released
+  /// packages, returns whether anything changed.
+  fn update_dependencies(&mut self, releases: &[Released]) -> bool {
+    let mut tables: Vec<&mut dyn TableLike> = Vec::new();
+
+    for (key, value) in self.content.as_table_mut().iter_mut() {
+      match key.get() {
+        "workspace" => {
+          tables.extend(get_mut(value, &["dependencies"]).and_then(Item::as_table_like_mut))
+        }
+        "target" => {
+          for target in value
+            .as_table_like_mut()
+            .into_iter()
+            .flat_map(|targets| targets.iter_mut().map(|(_, target)| target))
+          {
+            let target = target
+              .as_table_like_mut()
+              .into_iter()
+              .flat_map(|target| target.iter_mut());
+
+            tables.extend(
+              target
+                .filter(|(key, _)| DEPENDENCY_TABLES.contains(&key.get()))
+                .filter_map(|(_, dependencies)| dependencies.as_table_like_mut()),
+            );
+          }
+        }
+        key if DEPENDENCY_TABLES.contains(&key) => tables.extend(value.as_table_like_mut()),
+        _ => (),
+      }
+    }
+
+    let mut changed = false;
+
+    for dependencies in tables {
+      for (name, dependency) in dependencies.iter_mut() {
+        let Some(dependency) = dependency.as_table_like_mut()
+        else {
+          continue;
+        };
+
+        // Renamed dependencies have the real name in `package`.
+        let package = dependency
+          .get("package")
+          .and_then(Item::as_str)
+          .unwrap_or(name.get());
+
+        let Some(released) = releases.iter().find(|released| released.name == package)
+        else {
+          continue;
+        };
+
+        if !dependency.contains_key("path") {
+          continue;
+        }
+
+        let Some(requirement) = dependency.get_mut("version")
+        else {
+          continue;
+        };
+
+        if let Some(updated) = requirement
+          .as_str()
+          .and_then(|req| update_requirement(req, &released.new_version))
+        {
+          set_string(requirement, &updated);
+          changed = true;
+        }
+      }
+    }
+
+    changed
+  }
+}
+
+/// Looks up a nested item, without creating the missing tables along the way.
+fn get_mut<'a>(item: &'a mut Item, keys: &[&str]) -> Option<&'a mut Item> {
+  keys
+    .iter()
+    .try_fold(item, |item, key| item.as_table_like_mut()?.get_mut(key))
+}
+
+/// Replaces the string, keeping the comments and whitespaces around it.
+fn set_string(item: &mut Item, string: &str) {
+  if let Some(value) = item.as_value_mut() {
+    let decor = value.decor().clone();
+    *value = string.into();
+    *value.decor_mut() = decor;
+  }
+}
+
+/// Keeps the operator of the requirement, e.g. `=1.2.3` becomes `=1.3.0`.
+/// Requirements that aren't a single complete version, e.g. `1.2`, `*`
+/// or `>=1.0.0, <2.0.0`, are kept as is.
+fn update_requirement(requirement: &str, version: &str) -> Option<String> {
+  let (operator, current) = requirement.split_at(requirement.find(|c: char| c.is_ascii_digit())?);
+
+  if !matches!(operator.trim_end(), "" | "^" | "~" | "=" | ">=")
+    || string_to_version(current).is_err()
+  {
+    return None;
+  }
+
+  Some(format!("{operator}{version}"))
 }
 
-pub fn get_current_version() -> Result<String> {
-  let content = read_file(&mut open_cargo_toml()?)?;
-  let content: toml::Value = toml::from_str(&content)?;
+/// The root manifest first, followed by the members of the workspace.
+fn manifests() -> Result<Vec<Manifest>> {
+  let root = Manifest::read(CARGO_TOML)?;
+
+  let mut manifests = root
+    .members()?
+    .iter()
+    .map(|path| Manifest::read(path))
+    .collect::<Result<Vec<_>>>()?;
+
+  manifests.insert(0, root);
+
+  Ok(manifests)
+}
+
+/// Reads the version of the root package, or the one shared by the workspace members:
+/// `[workspace.package].version` or the version every member agrees on.
+fn current_version(manifests: &[Manifest]) -> Result<String> {
+  let root = &manifests[0];
+
+  if let Some(version) = root.version().or(root.workspace_version()) {
+    return Ok(version.to_string());
+  }
+
+  let mut versions = manifests.iter().filter_map(Manifest::version);
 
-  let version = content
-    .get("package")
-    .and_then(|package| package.get("version"))
-    .and_then(|version| version.as_str())
-    .ok_or_else(|| anyhow::anyhow!("'Cargo.toml' is missing 'version' property."))?;
+  let Some(version) = versions.next()
+  else {
+    anyhow::bail!("'{CARGO_TOML}' is missing 'version' property.");
+  };
+
+  if versions.any(|other| other != version) {
+    anyhow::bail!(
+      "members of the workspace have different versions, share one with '[workspace.package]'"
+    );
+  }
 
   Ok(version.to_string())
 }
 
-/// Edits the `Cargo.toml` file and updates the value of the `version` property.
+/// Every member of the workspace, each one with its own version.
+/// The root package of a non-virtual workspace is one of them, at `.`.
+pub fn packages() -> Result<Vec<Package>> {
+  let manifests = manifests()?;
+
+  manifests
+    .iter()
+    .filter_map(|manifest| Some((manifest, manifest.name()?)))
+    .map(|(manifest, name)| {
+      let Some(version) = manifest.version()
+      else {
+        anyhow::bail!(
+          "'{}' doesn't have its own version, which independent versioning requires",
+          manifest.path
+        );
+      };
+
+      let path = Path::new(&manifest.path)
+        .parent()
+        .filter(|path| !path.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+      Ok(Package {
+        name: name.to_string(),
+        path: path.to_string_lossy().to_string(),
+        version: string_to_version(version)
+          .with_context(|| format!("'{}' has an invalid version", manifest.path))?,
+      })
+    })
+    .collect()
+}
+
+pub fn get_current_version() -> Result<Version> {
+  let version = current_version(&manifests()?)?;
+  string_to_version(&version).with_context(|| format!("'{CARGO_TOML}' has an invalid version"))
+}
+
+/// Edits the `Cargo.toml` files and updates the value of the `version` property:
+/// the one of `[workspace.package]` and the one of every package released along.
+///
+/// Requirements of the `path` dependencies on these packages are updated too,
+/// so they keep pointing to the released version.
 pub fn bump_version(version: &str) -> Result<()> {
-  let mut file = open_cargo_toml()?;
+  let mut manifests = manifests()?;
+  let old_version = current_version(&manifests)?;
 
-  let content = read_file(&mut file)?;
-  let mut content: toml::Value = toml::from_str(&content)?;
+  // Packages that have the released version, inherited or not.
+  let releases = manifests
+    .iter()
+    .filter(|manifest| {
+      manifest.inherits_version() || manifest.version() == Some(old_version.as_str())
+    })
+    .filter_map(|manifest| manifest.name())
+    .map(|name| Released {
+      name: name.to_string(),
+      old_version: old_version.clone(),
+      new_version: version.to_string(),
+    })
+    .collect::<Vec<_>>();
 
-  let version_property = content
-    .get_mut("package")
-    .and_then(|package| package.get_mut("version"))
-    .ok_or_else(|| anyhow::anyhow!("'Cargo.toml' is missing 'version' property."))?;
+  for manifest in manifests.iter_mut() {
+    let mut changed = manifest.update_dependencies(&releases);
 
-  *version_property = toml::Value::String(version.to_string());
+    let content = manifest.content.as_item_mut();
 
-  let content = toml::to_string(&content)?;
-  write_file(&mut file, content)?;
+    if let Some(version_property) = get_mut(content, &["workspace", "package", "version"]) {
+      set_string(version_property, version);
+      changed = true;
+    }
+
+    if let Some(version_property) = get_mut(content, &["package", "version"])
+      .filter(|package_version| package_version.as_str() == Some(old_version.as_str()))
+    {
+      set_string(version_property, version);
+      changed = true;
+    }
+
+    if changed {
+      manifest.write()?;
+    }
+  }
 
   // We have to update the `Cargo.lock` file as well.
-  if !Command::new("cargo")
-    .arg("check")
-    .output()?
-    .status
-    .success()
-  {
-    return Err(anyhow::anyhow!("failed to update lockfile"));
+  update_lockfile(&releases)
+}
+
+/// Bumps the given members of the workspace, each one to its own version,
+/// along with the requirements of the `path` dependencies on them.
+pub fn bump_packages(releases: &[(String, String)]) -> Result<()> {
+  let mut manifests = manifests()?;
+
+  let releases = releases
+    .iter()
+    .map(|(name, new_version)| {
+      let old_version = manifests
+        .iter()
+        .find(|manifest| manifest.name() == Some(name.as_str()))
+        .and_then(Manifest::version)
+        .with_context(|| format!("package '{name}' doesn't have its own version"))?;
+
+      Ok(Released {
+        name: name.clone(),
+        old_version: old_version.to_string(),
+        new_version: new_version.clone(),
+      })
+    })
+    .collect::<Result<Vec<_>>>()?;
+
+  for manifest in manifests.iter_mut() {
+    let mut changed = manifest.update_dependencies(&releases);
+
+    let released = releases
+      .iter()
+      .find(|released| manifest.name() == Some(released.name.as_str()));
+
+    if let Some(released) = released {
+      if let Some(version_property) =
+        get_mut(manifest.content.as_item_mut(), &["package", "version"])
+      {
+        set_string(version_property, &released.new_version);
+        changed = true;
+      }
+    }
+
+    if changed {
+      manifest.write()?;
+    }
   }
 
-  Ok(())
+  update_lockfile(&releases)
+}
+
+/// Updates the version of the released packages in `Cargo.lock`, directly
+/// instead of running cargo, so it's fast and works offline.
+fn update_lockfile(releases: &[Released]) -> Result<()> {
+  // Libraries don't always commit their lockfile.
+  if !file_exists(CARGO_LOCK) {
+    return Ok(());
+  }
+
+  let content = read_file(&mut open_file(CARGO_LOCK)?)?;
+  let mut lockfile = content
+    .parse::<DocumentMut>()
+    .with_context(|| format!("'{CARGO_LOCK}' is not valid TOML"))?;
+
+  update_lockfile_versions(&mut lockfile, releases);
+  write_file(CARGO_LOCK, lockfile.to_string())
+}
+
+/// Updates the versions of the released packages of the workspace in the lockfile,
+/// and the references to them from other entries.
+fn update_lockfile_versions(lockfile: &mut DocumentMut, releases: &[Released]) {
+  let released = |name: &str, version: &str| {
+    releases
+      .iter()
+      .find(|released| released.name == name && released.old_version == version)
+  };
+
+  let Some(entries) = lockfile
+    .get_mut("package")
+    .and_then(Item::as_array_of_tables_mut)
+  else {
+    return;
+  };
+
+  for entry in entries.iter_mut() {
+    // Packages of the workspace are the only ones without a `source`.
+    let is_local = !entry.contains_key("source");
+    let name = entry.get("name").and_then(Item::as_str).unwrap_or_default();
+    let version = entry
+      .get("version")
+      .and_then(Item::as_str)
+      .unwrap_or_default();
+
+    if let Some(released) = released(name, version).filter(|_| is_local) {
+      set_string(&mut entry["version"], &released.new_version);
+    }
+
+    // Dependencies are written as `name version` when the name alone is ambiguous.
+    let Some(dependencies) = entry.get_mut("dependencies").and_then(Item::as_array_mut)
+    else {
+      continue;
+    };
+
+    for dependency in dependencies.iter_mut() {
+      let updated = match dependency
+        .as_str()
+        .map(|dependency| dependency.split(' ').collect::<Vec<_>>())
+      {
+        Some(parts) if parts.len() == 2 => match released(parts[0], parts[1]) {
+          Some(released) => format!("{} {}", parts[0], released.new_version),
+          None => continue,
+        },
+        _ => continue,
+      };
+
+      let decor = dependency.decor().clone();
+      *dependency = updated.into();
+      *dependency.decor_mut() = decor;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn manifest(content: &str) -> Manifest {
+    Manifest {
+      path: CARGO_TOML.into(),
+      content: content.parse().unwrap(),
+    }
+  }
+
+  fn released(name: &str) -> Released {
+    Released {
+      name: name.into(),
+      old_version: "1.0.0".into(),
+      new_version: "1.1.0".into(),
+    }
+  }
+
+  #[test]
+  fn update_dependencies_only_changes_versions() {
+    let mut manifest = manifest(
+      r#"# The application.
+[package]
+name = "app" # binary
+version = "1.0.0"
+
+[dependencies]
+core = { path = "../core", version = "=1.0.0" }   # pinned
+renamed = { package = "core", path = "../core", version = "^1.0.0" }
+bounded = { package = "core", path = "../core", version = ">=1.0.0, <2.0.0" }
+partial = { package = "core", path = "../core", version = "1.0" }
+registry = { package = "core", version = "1.0.0" }
+other = { path = "../other", version = "1.0.0" }
+
+[dev-dependencies]
+core.path = "../core"
+core.version = "~1.0.0" # dotted
+
+[target.'cfg(unix)'.build-dependencies.core]
+path = "../core"
+version   =   "1.0.0"   # spaced
+
+[workspace.dependencies]
+core = { path = "core", version = "1.0.0" }
+"#,
+    );
+
+    assert!(manifest.update_dependencies(&[released("core")]));
+    assert_eq!(
+      manifest.content.to_string(),
+      r#"# The application.
+[package]
+name = "app" # binary
+version = "1.0.0"
+
+[dependencies]
+core = { path = "../core", version = "=1.1.0" }   # pinned
+renamed = { package = "core", path = "../core", version = "^1.1.0" }
+bounded = { package = "core", path = "../core", version = ">=1.0.0, <2.0.0" }
+partial = { package = "core", path = "../core", version = "1.0" }
+registry = { package = "core", version = "1.0.0" }
+other = { path = "../other", version = "1.0.0" }
+
+[dev-dependencies]
+core.path = "../core"
+core.version = "~1.1.0" # dotted
+
+[target.'cfg(unix)'.build-dependencies.core]
+path = "../core"
+version   =   "1.1.0"   # spaced
+
+[workspace.dependencies]
+core = { path = "core", version = "1.1.0" }
+"#
+    );
+  }
+
+  #[test]
+  fn update_dependencies_without_released_packages() {
+    let content = "[dependencies]\ncore = { path = \"../core\", version = \"1.0.0\" }\n";
+    let mut manifest = manifest(content);
+
+    assert!(!manifest.update_dependencies(&[released("other")]));
+    assert_eq!(manifest.content.to_string(), content);
+  }
+
+  #[test]
+  fn update_lockfile_versions_only_changes_the_workspace() {
+    let mut lockfile = r#"# This file is automatically @generated by Cargo.
+version = 3
+
+[[package]]
+name = "app"
+version = "1.0.0"
+dependencies = [
+ "core 1.0.0",
+ "core 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+ "other",
+]
+
+[[package]]
+name = "core"
+version = "1.0.0"
+
+[[package]]
+name = "core"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "0000000000000000000000000000000000000000000000000000000000000000"
+
+[[package]]
+name = "other"
+version = "1.0.0"
+dependencies = [
+ "core 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+]
+"#
+    .parse::<DocumentMut>()
+    .unwrap();
+
+    update_lockfile_versions(&mut lockfile, &[released("app"), released("core")]);
+    assert_eq!(
+      lockfile.to_string(),
+      r#"# This file is automatically @generated by Cargo.
+version = 3
+
+[[package]]
+name = "app"
+version = "1.1.0"
+dependencies = [
+ "core 1.1.0",
+ "core 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+ "other",
+]
+
+[[package]]
+name = "core"
+version = "1.1.0"
+
+[[package]]
+name = "core"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "0000000000000000000000000000000000000000000000000000000000000000"
+
+[[package]]
+name = "other"
+version = "1.0.0"
+dependencies = [
+ "core 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+]
+"#
+    );
+  }
+
+  #[test]
+  fn set_string_keeps_the_decor() {
+    let mut manifest = manifest(
+      "package.name = \"app\"\npackage.version   = \"1.0.0\"  # dotted\n\n[workspace.package]\nversion = { workspace = false }\n",
+    );
+
+    let content = manifest.content.as_item_mut();
+    set_string(get_mut(content, &["package", "version"]).unwrap(), "1.1.0");
+    assert!(get_mut(content, &["workspace", "package", "missing"]).is_none());
+
+    assert_eq!(
+      manifest.content.to_string(),
+      "package.name = \"app\"\npackage.version   = \"1.1.0\"  # dotted\n\n[workspace.package]\nversion = { workspace = false }\n"
+    );
+  }
+
+  #[test]
+  fn get_mut_doesnt_create_tables() {
+    let content = "[package]\nname = \"app\"\n";
+    let mut manifest = manifest(content);
+
+    assert!(get_mut(
+      manifest.content.as_item_mut(),
+      &["workspace", "package", "version"]
+    )
+    .is_none());
+    assert_eq!(manifest.content.to_string(), content);
+  }
+
+  #[test]
+  fn update_requirement_keeps_the_operator() {
+    assert_eq!(
+      update_requirement("1.0.0", "1.1.0").as_deref(),
+      Some("1.1.0")
+    );
+    assert_eq!(
+      update_requirement("^1.0.0", "1.1.0").as_deref(),
+      Some("^1.1.0")
+    );
+    assert_eq!(
+      update_requirement("= 1.0.0", "1.1.0").as_deref(),
+      Some("= 1.1.0")
+    );
+    assert_eq!(
+      update_requirement(">=1.0.0", "1.1.0").as_deref(),
+      Some(">=1.1.0")
+    );
+    assert_eq!(update_requirement("1.0", "1.1.0"), None);
+    assert_eq!(update_requirement("*", "1.1.0"), None);
+    assert_eq!(update_requirement("<2.0.0", "1.1.0"), None);
+    assert_eq!(update_requirement(">=1.0.0, <2.0.0", "1.1.0"), None);
+  }
 }
@@ -1,36 +1,57 @@
-use crate::utils::{open_readme, read_file, write_file};
-use crate::{git, version::DEFAULT};
+use crate::utils::{
+  file_exists, open_readme, program_exists, read_file, run_check, write_file, README_MD,
+};
+use crate::{
+  git,
+  tag::TagTemplate,
+  version::{Version, DEFAULT},
+};
 use anyhow::Result;
 
 pub const PACKAGE_SWIFT: &str = "Package.swift";
 
-pub fn get_current_version() -> String {
-  let tags = git::tags();
-
-  if let Some(latest) = tags.first() {
-    latest.to_string()
+/// Builds the package and runs its tests.
+pub fn run_checks() -> Result<()> {
+  if !program_exists("swift") {
+    anyhow::bail!(
+      "the Swift toolchain is required to run the checks, install it or disable 'steps.checks'"
+    );
   }
-  else {
-    DEFAULT.into()
+
+  run_check("build the package", "swift", &["build"])?;
+  run_check("pass tests", "swift", &["test"])?;
+
+  Ok(())
+}
+
+/// Swift packages are versioned through git tags only,
+/// so the highest tagged version is the current version.
+pub fn get_current_version(template: &TagTemplate) -> Result<Version> {
+  match git::version_tags(template).into_iter().max() {
+    Some(latest) => Ok(latest),
+    None => DEFAULT.parse(),
   }
 }
 
 fn bump_readme(old_version: &str, new_version: &str) -> Result<()> {
-  let mut file = open_readme()?;
-  let content = read_file(&mut file)?;
+  if !file_exists(README_MD) {
+    return Ok(());
+  }
+
+  let content = read_file(&mut open_readme()?)?;
 
   // replace for installation section
   let from = format!("from: \"{old_version}\"");
   let to = format!("from: \"{new_version}\"");
   let content = content.replace(&from, &to);
 
-  write_file(&mut file, content)?;
+  write_file(README_MD, content)?;
 
   Ok(())
 }
 
-pub fn bump_version(new_version: &str) -> Result<()> {
-  let old_version = get_current_version();
+pub fn bump_version(template: &TagTemplate, new_version: &str) -> Result<()> {
+  let old_version = get_current_version(template)?.to_string();
 
   bump_readme(&old_version, new_version)?;
 
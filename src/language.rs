@@ -1,15 +1,21 @@
 use crate::{
-  implementations::{js, kotlin, rust, swift},
+  config::Config,
+  implementations::{js, python, rust, swift},
   utils::file_exists,
 };
 use anyhow::Result;
+use serde::Deserialize;
 use std::fmt;
 
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Language {
   Rust,
   Kotlin,
+  #[serde(rename = "js", alias = "ts")]
   JsTs,
   Swift,
+  Python,
 }
 
 impl fmt::Display for Language {
@@ -22,29 +28,36 @@ impl fmt::Display for Language {
         Self::Kotlin => "Kotlin",
         Self::JsTs => "JS/TS",
         Self::Swift => "Swift",
+        Self::Python => "Python",
       }
     )
   }
 }
 
-pub fn detect_language() -> Result<Language> {
-  if file_exists(js::PACKAGE_JSON) {
-    return Ok(Language::JsTs);
+/// Uses the languages of the configuration, detects them from the files otherwise:
+/// a repository can have several, e.g. a Tauri app with `Cargo.toml` and `package.json`.
+pub fn detect_languages(config: &Config) -> Result<Vec<Language>> {
+  if !config.language.is_empty() {
+    return Ok(config.language.clone());
   }
 
-  if file_exists(kotlin::BUILD_GRADLE_KTS) {
-    return Ok(Language::Kotlin);
-  }
+  let detected = [
+    (Language::JsTs, file_exists(js::PACKAGE_JSON)),
+    (Language::Kotlin, file_exists(&config.kotlin.build_file)),
+    (Language::Rust, file_exists(rust::CARGO_TOML)),
+    (Language::Python, python::is_project()),
+    (Language::Swift, file_exists(swift::PACKAGE_SWIFT)),
+  ];
 
-  if file_exists(rust::CARGO_TOML) {
-    return Ok(Language::Rust);
-  }
+  let languages = detected
+    .into_iter()
+    .filter(|(_, is_detected)| *is_detected)
+    .map(|(language, _)| language)
+    .collect::<Vec<_>>();
 
-  if file_exists(swift::PACKAGE_SWIFT) {
-    return Ok(Language::Swift);
+  if languages.is_empty() {
+    anyhow::bail!("couldn't detect the language, probably not supported");
   }
 
-  Err(anyhow::anyhow!(
-    "couldn't detect the language, probably not supported"
-  ))
+  Ok(languages)
 }
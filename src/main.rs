@@ -1,49 +1,137 @@
+use clap::Parser;
 use colored::Colorize;
 use spinners::{Spinner, Spinners};
+use std::path::Path;
+
+mod dry_run;
+mod rollback;
+use rollback::Undo;
 
 mod utils;
+
+mod config;
+use config::{Config, Versioning, MENTOR_TOML};
+
 mod version;
+use version::bump_version;
+
+mod conventional;
+use conventional::Analysis;
+
+mod changelog;
+use changelog::CHANGELOG_MD;
 
 mod git;
-use git::{diff, git};
+
+mod tag;
+use tag::TagTemplate;
+
+mod remote;
+use remote::Remote;
 
 mod github;
-use github::open_create_release;
+use github::{publish_release, Release};
 
 mod cli;
-use cli::prompt_new_version;
+use cli::{
+  confirm_packages, confirm_release, parse_new_version, prompt_new_version, Args, Command,
+};
+
+mod state;
+use state::{State, Step};
 
 mod language;
-use language::{detect_language, Language};
+use language::{detect_languages, Language};
 
 mod implementations;
 use implementations::js;
 use implementations::kotlin;
+use implementations::python;
 use implementations::rust;
 use implementations::swift;
 
 fn main() -> anyhow::Result<()> {
+  let args = Args::parse();
+
+  if args.dry_run {
+    dry_run::enable();
+    println!(
+      "{}",
+      "Running in dry-run mode, nothing will be modified.".yellow()
+    );
+  }
+
+  let result = match args.command {
+    Some(Command::Resume) => resume(),
+    None => release(&args),
+  };
+
+  // Don't leave the repository half-released.
+  if result.is_err() {
+    rollback::rollback();
+  }
+
+  result
+}
+
+fn release(args: &Args) -> anyhow::Result<()> {
+  // Releasing again would bump the version a second time.
+  if let Some(state) = State::load()? {
+    anyhow::bail!(
+      "the release of {} is still in progress, run 'mentor resume' to finish it",
+      state.tags().join(", ")
+    );
+  }
+
+  let config = Config::load()?;
+  let template = config.tag_template()?;
+
+  let remote_name = args.remote.as_deref().unwrap_or(&config.remote);
+
+  // Only required to create the release, otherwise it's only used for links.
+  let remote = if config.steps.release {
+    Some(git::remote(remote_name)?)
+  }
+  else {
+    git::remote(remote_name).ok()
+  };
+
   //
-  // Detect the language.
+  // Detect the languages.
   //
 
-  let language = detect_language()?;
-  println!("Automatically detected language {language}");
+  let languages = detect_languages(&config)?;
+  let names = languages
+    .iter()
+    .map(Language::to_string)
+    .collect::<Vec<_>>()
+    .join(", ");
 
-  {
-    let mut spinner = Spinner::new(Spinners::Dots, "Running checks for this language...".into());
+  if !config.language.is_empty() {
+    println!("Using {names} from the configuration");
+  }
+  else {
+    println!("Automatically detected {names}");
+  }
 
-    match language {
-      Language::JsTs => js::run_checks()?,
-      Language::Kotlin => (),
-      Language::Rust => (),
-      Language::Swift => (), // TODO: run `swift test`
+  if config.steps.checks {
+    // No spinner here, the output of the checks is streamed.
+    println!("Running checks for {names}...");
+
+    for language in &languages {
+      match language {
+        Language::JsTs => js::run_checks(&config.js)?,
+        Language::Kotlin => kotlin::run_checks()?,
+        Language::Python => python::run_checks()?,
+        Language::Rust => rust::run_checks(&config.rust)?,
+        Language::Swift => swift::run_checks()?,
+      }
     }
 
-    spinner.stop_with_message("Checks are passing.".green().to_string());
+    println!("{}", "Checks are passing.".green());
   }
 
-  {
+  if config.steps.status {
     let mut spinner = Spinner::new(Spinners::Dots, "Checking repository status...".into());
 
     // Check if repo is dirty: has uncommitted changes.
@@ -59,7 +147,7 @@ fn main() -> anyhow::Result<()> {
 
     // Check if local is behind remote.
     let branch_name = git::branch_name();
-    if git::is_behind_upstream(&branch_name)? {
+    if git::is_behind_upstream(remote_name, &branch_name)? {
       spinner.stop_with_message(
         format!("Local branch '{branch_name}' is behind its remote. Please pull changes first.",)
           .red()
@@ -72,71 +160,390 @@ fn main() -> anyhow::Result<()> {
     spinner.stop_with_message("Repository is clean and up to date.".green().to_string());
   }
 
+  // Packages of the workspace are released on their own.
+  if config.versioning == Versioning::Independent {
+    let [language] = languages[..]
+    else {
+      anyhow::bail!(
+        "independent versioning works with a single language, pick it with 'language' in '{MENTOR_TOML}'"
+      );
+    };
+
+    return release_packages(args, &config, language, remote.as_ref(), remote_name);
+  }
+
   //
-  // Read the current version.
+  // Read the current version, every language has to agree on it
+  // since they're released together.
   //
 
-  let old_version = match language {
-    Language::JsTs => js::get_current_version()?,
-    Language::Kotlin => kotlin::get_current_version()?,
-    Language::Rust => rust::get_current_version()?,
-    Language::Swift => swift::get_current_version(),
+  let mut versions = Vec::new();
+
+  for language in &languages {
+    let version = match language {
+      Language::JsTs => js::get_current_version()?,
+      Language::Kotlin => kotlin::get_current_version(&config.kotlin)?,
+      Language::Python => match python::get_current_version()? {
+        Some(version) => version,
+        None => {
+          let notice =
+            "Python version is set by the build backend, e.g. from git tags, it's left as is.";
+          println!("{}", notice.yellow());
+          continue;
+        }
+      },
+      Language::Rust => rust::get_current_version()?,
+      Language::Swift => swift::get_current_version(&template)?,
+    };
+
+    versions.push((language, version));
+  }
+
+  // Without any version in the files, the tags are the only source.
+  let old_version = match versions.first() {
+    Some((_, version)) => version.clone(),
+    None => match git::version_tags(&template).into_iter().max() {
+      Some(latest) => latest,
+      None => version::DEFAULT.parse()?,
+    },
   };
 
+  if versions.iter().any(|(_, version)| *version != old_version) {
+    let found = versions
+      .iter()
+      .map(|(language, version)| format!("{language} is at {version}"))
+      .collect::<Vec<_>>()
+      .join(", ");
+
+    anyhow::bail!("versions don't agree: {found}, align them before releasing");
+  }
+
   //
-  // Bump the version, by asking the user.
+  // Bump the version, from the arguments or by asking the user.
   //
 
-  let new_version = prompt_new_version(&old_version);
+  let tags = git::version_tags(&template);
+  let previous_tag = git::previous_release_tag(&template, &old_version);
 
-  match language {
-    Language::JsTs => js::bump_version(&new_version)?,
-    Language::Kotlin => kotlin::bump_version(&new_version)?,
-    Language::Rust => rust::bump_version(&new_version)?,
-    Language::Swift => swift::bump_version(&new_version)?,
+  // Look at the commits since the last release to recommend a bump.
+  let commits = git::commits_since(previous_tag.as_deref(), None);
+  let analysis = Analysis::new(&commits);
+  let recommended = analysis.recommend_bump(&old_version);
+  println!("Found {analysis} since {old_version}.");
+
+  let new_version = match &args.target {
+    Some(input) => parse_new_version(input, args.channel.as_deref(), &old_version, &tags)?,
+    None if args.auto => bump_version(&old_version, &recommended, &tags),
+    None => prompt_new_version(&old_version, args.channel.as_deref(), &tags, &recommended)?,
+  };
+
+  if !args.yes && !confirm_release(&old_version, &new_version)? {
+    println!("{}", "Release aborted, nothing was changed.".yellow());
+    return Ok(());
   }
 
-  //
-  // Delete previous tag, if exists.
-  //
+  let tag = template.format(&new_version);
+
+  // Generated before the release commit, from the same commits we analyzed.
+  let release_body = changelog::generate(
+    remote.as_ref(),
+    &commits,
+    &old_version,
+    previous_tag.as_deref(),
+    &new_version,
+    &tag,
+  )?;
+
+  if args.changelog || config.steps.changelog {
+    changelog::update_changelog_file(
+      CHANGELOG_MD,
+      remote.as_ref(),
+      &release_body,
+      previous_tag.as_deref(),
+      &new_version,
+      &tag,
+    )?;
+  }
+
+  let prerelease = new_version.is_prerelease();
+  let new_version = new_version.to_string();
+
+  for language in &languages {
+    match language {
+      Language::JsTs => js::bump_version(&new_version)?,
+      Language::Kotlin => kotlin::bump_version(&config.kotlin, &new_version)?,
+      Language::Python => python::bump_version(&new_version)?,
+      Language::Rust => rust::bump_version(&new_version)?,
+      Language::Swift => swift::bump_version(&template, &new_version)?,
+    }
+  }
 
-  _ = git(&["tag", "-d", &new_version]);
+  let commit_message = config.expand(config.commit_message(), &new_version);
+  let tag_message = config.expand(&config.tag_message, &new_version);
 
+  let release = Release {
+    name: config.expand(&config.release_name, &new_version),
+    tag_name: tag,
+    body: release_body,
+    prerelease,
+    draft: args.draft,
+  };
+
+  finish(
+    &config,
+    remote_name,
+    &commit_message,
+    vec![(release, tag_message)],
+  )
+}
+
+/// Releases every package of the workspace that changed since its last release,
+/// each one with its own version and tag, in a single release commit.
+fn release_packages(
+  args: &Args,
+  config: &Config,
+  language: Language,
+  remote: Option<&Remote>,
+  remote_name: &str,
+) -> anyhow::Result<()> {
+  let packages = match language {
+    Language::JsTs => js::packages()?,
+    Language::Rust => rust::packages()?,
+    _ => anyhow::bail!("independent versioning isn't supported for {language} projects"),
+  };
+
+  let mut bumps = Vec::new();
+
+  for package in &packages {
+    let template = TagTemplate::new(&config.tag, Some(&package.name))?;
+    let previous_tag = git::previous_release_tag(&template, &package.version);
+
+    // Packages that were never released are always part of the release.
+    if let Some(previous_tag) = &previous_tag {
+      if git::changed_files(previous_tag, &package.path).is_empty() {
+        continue;
+      }
+    }
+
+    let tags = git::version_tags(&template);
+    let commits = git::commits_since(previous_tag.as_deref(), Some(&package.path));
+    let analysis = Analysis::new(&commits);
+    let recommended = analysis.recommend_bump(&package.version);
+    println!(
+      "{}: found {analysis} since {}.",
+      package.name, package.version
+    );
+
+    let new_version = match &args.target {
+      Some(input) => parse_new_version(input, args.channel.as_deref(), &package.version, &tags)?,
+      None if args.auto => bump_version(&package.version, &recommended, &tags),
+      None => prompt_new_version(
+        &package.version,
+        args.channel.as_deref(),
+        &tags,
+        &recommended,
+      )?,
+    };
+
+    bumps.push((package, new_version, template, previous_tag, commits));
+  }
+
+  if bumps.is_empty() {
+    println!("{}", "No package changed since its last release.".yellow());
+    return Ok(());
+  }
+
+  let summary = bumps
+    .iter()
+    .map(|(package, new_version, ..)| {
+      format!("{} {} -> {new_version}", package.name, package.version)
+    })
+    .collect::<Vec<_>>();
+
+  if !args.yes && !confirm_packages(&summary)? {
+    println!("{}", "Release aborted, nothing was changed.".yellow());
+    return Ok(());
+  }
+
+  let mut releases = Vec::new();
+
+  for (package, new_version, template, previous_tag, commits) in &bumps {
+    let tag = template.format(new_version);
+
+    let release_body = changelog::generate(
+      remote,
+      commits,
+      &package.version,
+      previous_tag.as_deref(),
+      new_version,
+      &tag,
+    )?;
+
+    if args.changelog || config.steps.changelog {
+      let path = Path::new(&package.path).join(CHANGELOG_MD);
+
+      changelog::update_changelog_file(
+        &path.to_string_lossy(),
+        remote,
+        &release_body,
+        previous_tag.as_deref(),
+        new_version,
+        &tag,
+      )?;
+    }
+
+    let expand = |template: &str| {
+      template
+        .replace("{version}", &new_version.to_string())
+        .replace("{package}", &package.name)
+    };
+
+    let release = Release {
+      name: expand(&config.release_name),
+      tag_name: tag,
+      body: release_body,
+      prerelease: new_version.is_prerelease(),
+      draft: args.draft,
+    };
+
+    releases.push((release, expand(&config.tag_message)));
+  }
+
+  let versions = bumps
+    .iter()
+    .map(|(package, new_version, ..)| (package.name.clone(), new_version.to_string()))
+    .collect::<Vec<_>>();
+
+  match language {
+    Language::JsTs => js::bump_packages(&versions)?,
+    Language::Rust => rust::bump_packages(&versions)?,
+    _ => unreachable!(),
+  }
+
+  let tags = releases
+    .iter()
+    .map(|(release, _)| release.tag_name.as_str())
+    .collect::<Vec<_>>();
+  let names = bumps
+    .iter()
+    .map(|(package, ..)| package.name.as_str())
+    .collect::<Vec<_>>();
+
+  let commit_message = config
+    .commit_message()
+    .replace("{version}", &tags.join(", "))
+    .replace("{package}", &names.join(", "));
+
+  finish(config, remote_name, &commit_message, releases)
+}
+
+/// Commits the release, tags it, then pushes it and creates it on GitHub.
+/// Every release comes with the message of its tag.
+fn finish(
+  config: &Config,
+  remote_name: &str,
+  commit_message: &str,
+  releases: Vec<(Release, String)>,
+) -> anyhow::Result<()> {
   //
-  // Commit, tag and push to origin.
+  // Commit, tag and push to the remote.
   //
 
-  let commit_message = format!("chore: release v{new_version}");
-  let tag_message = format!("Release v{new_version}");
   let branch_name = git::branch_name();
 
-  let commands = vec![
-    vec!["add", "-A"],
-    vec!["commit", "--allow-empty", "-m", &commit_message],
-    vec!["tag", "-a", &new_version, "-m", &tag_message],
-    vec!["push", "origin", &branch_name, "--tags"],
-  ];
+  if config.steps.commit {
+    rollback::record(Undo::RestoreIndex(git::write_tree()?));
+    git::run(&["add", "-A"])?;
 
-  for command in commands {
-    let output = git(&command);
+    git::run(&["commit", "--allow-empty", "-m", commit_message])?;
+    rollback::record(Undo::ResetCommit);
+  }
 
-    if !output.status.success() {
-      let stdout = String::from_utf8_lossy(&output.stdout);
-      let stderr = String::from_utf8_lossy(&output.stderr);
-      anyhow::bail!("stdout: {stdout} / stderr: {stderr}");
+  if config.steps.tag {
+    for (release, tag_message) in &releases {
+      let tag = &release.tag_name;
+
+      // Replace the previous tag, if exists.
+      rollback::record(Undo::RestoreTag {
+        tag: tag.clone(),
+        previous: git::tag_object(tag),
+      });
+
+      _ = git::git_write(&["tag", "-d", tag]);
+      git::run(&["tag", "-a", tag, "-m", tag_message])?;
     }
   }
 
+  // Remaining steps are saved, so they can be resumed if the run dies.
+  let mut state = State {
+    remote: remote_name.to_string(),
+    branch: branch_name,
+    releases: releases.into_iter().map(|(release, _)| release).collect(),
+    pending: [
+      (Step::Push, config.steps.push),
+      (Step::Release, config.steps.release),
+    ]
+    .into_iter()
+    .filter_map(|(step, enabled)| enabled.then_some(step))
+    .collect(),
+  };
+
+  if !state.pending.is_empty() {
+    state.save()?;
+  }
+
+  distribute(&mut state)
+}
+
+/// Continues the release saved in the state file, from its last completed step.
+fn resume() -> anyhow::Result<()> {
+  let Some(mut state) = State::load()?
+  else {
+    anyhow::bail!("there's no release to resume");
+  };
+
+  let tags = state.tags();
+  if let Some(tag) = tags.iter().find(|tag| git::tag_object(tag).is_none()) {
+    anyhow::bail!("tag {tag} of the release to resume doesn't exist anymore");
+  }
+
+  println!("Resuming the release of {}", tags.join(", "));
+
+  distribute(&mut state)
+}
+
+/// Pushes the release and creates it on GitHub, according to the pending steps.
+fn distribute(state: &mut State) -> anyhow::Result<()> {
+  if state.is_pending(Step::Push) {
+    // Either everything is pushed or nothing is, so a failure can be rolled back.
+    git::run(&["push", "--atomic", &state.remote, &state.branch, "--tags"])?;
+
+    // The release is out, there's no going back from here.
+    rollback::commit();
+    state.complete(Step::Push)?;
+  }
+
   //
   // Make a release on GitHub.
   //
 
-  let release_body = diff(&old_version, &new_version);
-  let release_name = format!("v{new_version}");
-  open_create_release(release_body, new_version, release_name);
+  if state.is_pending(Step::Release) {
+    let remote = git::remote(&state.remote)?;
+
+    for release in &state.releases {
+      publish_release(&remote, release);
+    }
+
+    state.complete(Step::Release)?;
+  }
 
   // Show an exit message, the CLI has finished its job.
-  println!("{}", "Release is now being distributed !".green());
+  if dry_run::is_enabled() {
+    println!("{}", "Dry-run finished, nothing was modified.".green());
+  }
+  else {
+    println!("{}", "Release is now being distributed !".green());
+  }
 
   Ok(())
 }
@@ -0,0 +1,139 @@
+use anyhow::Result;
+
+/// A repository hosted on GitHub, or on a GitHub Enterprise instance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Remote {
+  pub host: String,
+  pub owner: String,
+  pub repo: String,
+}
+
+impl Remote {
+  /// Parses the URL of a git remote, supported forms are:
+  ///
+  /// - `https://github.com/owner/repo.git`, with optional credentials
+  /// - `ssh://git@github.com:22/owner/repo.git`, also `git://` and `git+ssh://`
+  /// - `git@github.com:owner/repo.git`, the scp-like syntax
+  ///
+  /// The `.git` suffix is always optional.
+  pub fn parse(url: &str) -> Result<Self> {
+    let url = url.trim();
+
+    let (authority, path) = match url.split_once("://") {
+      Some((_scheme, rest)) => rest
+        .split_once('/')
+        .ok_or_else(|| anyhow::anyhow!("remote URL '{url}' is missing the repository path"))?,
+      // scp-like syntax: `[user@]host:path`.
+      None => url
+        .split_once(':')
+        .filter(|(host, _)| !host.contains('/'))
+        .ok_or_else(|| anyhow::anyhow!("remote URL '{url}' is not supported"))?,
+    };
+
+    // Remove the credentials and the port, if any.
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host);
+
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    let (owner, repo) = path.rsplit_once('/').ok_or_else(|| {
+      anyhow::anyhow!("remote URL '{url}' is missing the owner of the repository")
+    })?;
+
+    if host.is_empty() || owner.is_empty() || repo.is_empty() {
+      anyhow::bail!("remote URL '{url}' is not supported");
+    }
+
+    Ok(Self {
+      host: host.to_lowercase(),
+      owner: owner.to_string(),
+      repo: repo.to_string(),
+    })
+  }
+
+  /// URL of the repository on the web, e.g. `https://github.com/owner/repo`.
+  pub fn web_url(&self) -> String {
+    format!("https://{}/{}/{}", self.host, self.owner, self.repo)
+  }
+
+  /// Base URL of the REST API for this host.
+  pub fn api_url(&self) -> String {
+    if self.host == "github.com" {
+      "https://api.github.com".into()
+    }
+    else {
+      // GitHub Enterprise Server exposes the API on the same host.
+      format!("https://{}/api/v3", self.host)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn remote(host: &str, owner: &str, repo: &str) -> Remote {
+    Remote {
+      host: host.into(),
+      owner: owner.into(),
+      repo: repo.into(),
+    }
+  }
+
+  #[test]
+  fn parses_remote_urls() {
+    let github = remote("github.com", "owner", "repo");
+
+    for url in [
+      "https://github.com/owner/repo.git",
+      "https://github.com/owner/repo",
+      "https://github.com/owner/repo/",
+      "https://user:pw@github.com/owner/repo",
+      "git@github.com:owner/repo.git",
+      "git@github.com:owner/repo",
+      "ssh://git@github.com:22/owner/repo.git",
+      "git+ssh://git@GitHub.com/owner/repo.git",
+      "git://github.com/owner/repo.git",
+      "  https://github.com/owner/repo.git\n",
+    ] {
+      assert_eq!(Remote::parse(url).unwrap(), github, "{url}");
+    }
+
+    assert_eq!(
+      Remote::parse("https://ghe.example.com/team/project.git").unwrap(),
+      remote("ghe.example.com", "team", "project")
+    );
+    assert_eq!(
+      Remote::parse("git@ghe.example.com:team/project").unwrap(),
+      remote("ghe.example.com", "team", "project")
+    );
+  }
+
+  #[test]
+  fn rejects_unsupported_urls() {
+    for url in [
+      "",
+      "/home/user/repo",
+      "../repo.git",
+      "file:///srv/git/owner/repo.git",
+      "https://github.com",
+      "https://github.com/repo.git",
+      "git@github.com:repo.git",
+      "ssh://git@github.com:22/repo.git",
+    ] {
+      assert!(Remote::parse(url).is_err(), "{url}");
+    }
+  }
+
+  #[test]
+  fn builds_urls_of_the_host() {
+    let github = remote("github.com", "owner", "repo");
+    assert_eq!(github.web_url(), "https://github.com/owner/repo");
+    assert_eq!(github.api_url(), "https://api.github.com");
+
+    let enterprise = remote("ghe.example.com", "team", "project");
+    assert_eq!(enterprise.web_url(), "https://ghe.example.com/team/project");
+    assert_eq!(enterprise.api_url(), "https://ghe.example.com/api/v3");
+  }
+}
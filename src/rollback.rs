@@ -0,0 +1,102 @@
+use crate::{dry_run, git};
+use anyhow::Context;
+use colored::Colorize;
+use std::{fs, sync::Mutex};
+
+/// Compensating action of a step of the release, undoing what it did.
+pub enum Undo {
+  /// Puts back the previous content of the file, removes it when it didn't exist.
+  RestoreFile {
+    path: String,
+    content: Option<String>,
+  },
+  /// Puts back the index as it was before `git add`, from a `git write-tree`.
+  RestoreIndex(String),
+  /// Removes the release commit but keeps its changes, `git reset --soft HEAD~1`.
+  ResetCommit,
+  /// Deletes the release tag, or points it back to the tag it replaced.
+  RestoreTag {
+    tag: String,
+    previous: Option<String>,
+  },
+}
+
+/// Steps done so far, undone in reverse order when the release fails.
+static JOURNAL: Mutex<Vec<Undo>> = Mutex::new(Vec::new());
+
+/// Nothing is modified in dry-run mode, so there's nothing to record.
+pub fn record(undo: Undo) {
+  if dry_run::is_enabled() {
+    return;
+  }
+
+  JOURNAL.lock().unwrap().push(undo);
+}
+
+/// Keeps the current content of the file so it can be restored,
+/// only the content before the first change of the release is kept.
+pub fn record_file(path: &str) {
+  let already_recorded = JOURNAL
+    .lock()
+    .unwrap()
+    .iter()
+    .any(|undo| matches!(undo, Undo::RestoreFile { path: recorded, .. } if recorded == path));
+
+  if !already_recorded {
+    record(Undo::RestoreFile {
+      path: path.to_string(),
+      content: fs::read_to_string(path).ok(),
+    });
+  }
+}
+
+/// Forgets every recorded step, once the release is pushed it can't be undone.
+pub fn commit() {
+  JOURNAL.lock().unwrap().clear();
+}
+
+/// Undoes every recorded step, the latest first.
+///
+/// Failing actions are reported but don't stop the rollback,
+/// so we get as close as possible to the initial state.
+pub fn rollback() {
+  let journal = std::mem::take(&mut *JOURNAL.lock().unwrap());
+
+  if journal.is_empty() {
+    return;
+  }
+
+  println!(
+    "{}",
+    "Release failed, rolling back local changes...".yellow()
+  );
+
+  for undo in journal.into_iter().rev() {
+    let result = match &undo {
+      Undo::RestoreFile { path, content } => {
+        let log = format!("+> restore {path}");
+        println!("{}", log.bright_black());
+
+        match content {
+          Some(content) => fs::write(path, content),
+          None => fs::remove_file(path),
+        }
+        .with_context(|| format!("couldn't restore '{path}'"))
+      }
+      Undo::RestoreIndex(tree) => git::run(&["read-tree", tree]),
+      Undo::ResetCommit => git::run(&["reset", "--soft", "HEAD~1"]),
+      Undo::RestoreTag { tag, previous } => {
+        let reference = format!("refs/tags/{tag}");
+
+        match previous {
+          Some(previous) => git::run(&["update-ref", &reference, previous]),
+          None => git::run(&["update-ref", "-d", &reference]),
+        }
+      }
+    };
+
+    if let Err(error) = result {
+      println!("{}", format!("{error:#}").red());
+    }
+  }
+}
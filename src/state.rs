@@ -0,0 +1,99 @@
+use crate::{dry_run, git, github::Release, rollback, utils::file_exists};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// Name of the state file, stored within the `.git` directory.
+const STATE_FILE: &str = "mentor-release.json";
+
+/// Steps that are left once the release is committed and tagged.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Step {
+  Push,
+  Release,
+}
+
+/// Progress of a release, saved so it can be resumed with `mentor resume`
+/// when the run dies after tagging.
+#[derive(Serialize, Deserialize)]
+pub struct State {
+  pub remote: String,
+  pub branch: String,
+  /// A single release, or one per package with independent versioning.
+  pub releases: Vec<Release>,
+  /// Steps left to do, in order.
+  pub pending: Vec<Step>,
+}
+
+fn path() -> Result<String> {
+  git::git_path(STATE_FILE)
+}
+
+impl State {
+  /// Reads the state of the release in progress, if any.
+  pub fn load() -> Result<Option<Self>> {
+    let path = path()?;
+
+    let Ok(content) = fs::read_to_string(&path)
+    else {
+      return Ok(None);
+    };
+
+    let state = serde_json::from_str(&content)
+      .with_context(|| format!("invalid release state in '{path}', delete it to start over"))?;
+
+    Ok(Some(state))
+  }
+
+  /// Nothing is saved in dry-run mode, since nothing was released.
+  pub fn save(&self) -> Result<()> {
+    if dry_run::is_enabled() {
+      return Ok(());
+    }
+
+    let path = path()?;
+
+    // A release rolled back before being pushed has nothing to resume.
+    if !file_exists(&path) {
+      rollback::record_file(&path);
+    }
+
+    let content = serde_json::to_string_pretty(self)?;
+    fs::write(&path, content).with_context(|| format!("failed to write '{path}'"))
+  }
+
+  /// Tags of the releases.
+  pub fn tags(&self) -> Vec<&str> {
+    self
+      .releases
+      .iter()
+      .map(|release| release.tag_name.as_str())
+      .collect()
+  }
+
+  pub fn is_pending(&self, step: Step) -> bool {
+    self.pending.contains(&step)
+  }
+
+  /// Marks the step as done, the state is removed once every step is.
+  pub fn complete(&mut self, step: Step) -> Result<()> {
+    self.pending.retain(|pending| *pending != step);
+
+    if !self.pending.is_empty() {
+      return self.save();
+    }
+
+    if dry_run::is_enabled() {
+      return Ok(());
+    }
+
+    let path = path()?;
+    match fs::remove_file(&path) {
+      Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+        Err(error).with_context(|| format!("failed to remove '{path}'"))
+      }
+      _ => Ok(()),
+    }
+  }
+}
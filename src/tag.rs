@@ -0,0 +1,45 @@
+use crate::version::{string_to_version, Version};
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// Names the tags of the releases, e.g. `v{version}` or `{package}@{version}`.
+pub struct TagTemplate<'a> {
+  template: &'a str,
+  package: Option<&'a str>,
+  /// Matches the tags made from this template, to read their version back.
+  pattern: Regex,
+}
+
+impl<'a> TagTemplate<'a> {
+  /// The `package` is required whenever the template contains `{package}`.
+  pub fn new(template: &'a str, package: Option<&'a str>) -> Result<Self> {
+    let pattern = regex::escape(template)
+      .replace(r"\{version\}", "(?<version>.+)")
+      .replace(r"\{package\}", &regex::escape(package.unwrap_or_default()));
+
+    let pattern = Regex::new(&format!("^{pattern}$"))
+      .with_context(|| format!("tag template '{template}' can't be used to find the tags"))?;
+
+    Ok(Self {
+      template,
+      package,
+      pattern,
+    })
+  }
+
+  /// Builds the tag of the given version.
+  pub fn format(&self, version: &Version) -> String {
+    let tag = self.template.replace("{version}", &version.to_string());
+
+    match self.package {
+      Some(package) => tag.replace("{package}", package),
+      None => tag,
+    }
+  }
+
+  /// Reads the version back from a tag, `None` when the tag doesn't match the template.
+  pub fn parse(&self, tag: &str) -> Option<Version> {
+    let captures = self.pattern.captures(tag)?;
+    string_to_version(&captures["version"]).ok()
+  }
+}
@@ -1,8 +1,13 @@
-use anyhow::Result;
+use crate::{dry_run, rollback};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use similar::{ChangeTag, TextDiff};
 use std::{
-  fs::{File, OpenOptions},
-  io::{self, Read, Seek, SeekFrom, Write},
+  fs::{self, File},
+  io::{self, Read},
   path::Path,
+  process::Command,
+  time::{SystemTime, UNIX_EPOCH},
 };
 
 pub fn find_between(content: &str, start: &str, end: &str) -> String {
@@ -15,8 +20,10 @@ pub fn find_between(content: &str, start: &str, end: &str) -> String {
   content[start_index..end_index].to_string()
 }
 
+pub const README_MD: &str = "README.md";
+
 pub fn open_readme() -> io::Result<File> {
-  open_file("README.md")
+  open_file(README_MD)
 }
 
 pub fn file_exists(path: &str) -> bool {
@@ -24,12 +31,7 @@ pub fn file_exists(path: &str) -> bool {
 }
 
 pub fn open_file(path: &str) -> io::Result<File> {
-  OpenOptions::new()
-    .read(true)
-    .write(true)
-    .truncate(false)
-    .create(true)
-    .open(path)
+  File::open(path)
 }
 
 pub fn read_file(file: &mut File) -> Result<String> {
@@ -39,14 +41,100 @@ pub fn read_file(file: &mut File) -> Result<String> {
   Ok(buffer)
 }
 
-pub fn write_file(file: &mut File, content: String) -> Result<()> {
-  // Move cursor to the beginning.
-  file.seek(SeekFrom::Start(0))?;
-  file.set_len(0)?;
+/// Replaces the content of the file at `path`, creating it if needed.
+///
+/// In dry-run mode, the file is left untouched and
+/// a diff of the changes is printed instead.
+/// Otherwise its previous content is kept to be restored if the release fails.
+pub fn write_file(path: &str, content: String) -> Result<()> {
+  if dry_run::is_enabled() {
+    let previous = fs::read_to_string(path).unwrap_or_default();
+    print_diff(path, &previous, &content);
+
+    return Ok(());
+  }
+
+  rollback::record_file(path);
+  fs::write(path, content).with_context(|| format!("failed to write '{path}'"))?;
+
+  Ok(())
+}
+
+/// Whether the program can be run, used to detect the toolchains.
+pub fn program_exists(program: &str) -> bool {
+  Command::new(program).arg("--version").output().is_ok()
+}
+
+/// Runs a check of the project, e.g. its tests, with its output streamed to the terminal.
+/// The `description` tells which check failed, e.g. "pass the tests".
+pub fn run_check(description: &str, program: &str, args: &[&str]) -> Result<()> {
+  let log = format!("+> {program} {}", args.join(" "));
+  println!("{}", log.bright_black());
 
-  // Write the content to the file.
-  file.write_all(content.as_bytes())?;
-  file.flush()?;
+  let status = Command::new(program)
+    .args(args)
+    .status()
+    .with_context(|| format!("failed to run '{program}', make sure it's installed"))?;
+
+  if !status.success() {
+    anyhow::bail!(
+      "failed to {description}, see the output of '{program} {}' above",
+      args.join(" ")
+    );
+  }
 
   Ok(())
 }
+
+/// Prints an unified diff between two contents of the file at `path`.
+pub fn print_diff(path: &str, old: &str, new: &str) {
+  let log = format!("+> write {path} (dry-run)");
+  println!("{}", log.bright_black());
+
+  if old == new {
+    println!("{}", "   (no changes)".bright_black());
+    return;
+  }
+
+  let diff = TextDiff::from_lines(old, new);
+
+  println!("{}", format!("--- a/{path}").bold());
+  println!("{}", format!("+++ b/{path}").bold());
+
+  for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+    println!("{}", hunk.header().to_string().cyan());
+
+    for change in hunk.iter_changes() {
+      let line = format!("{}{}", change.tag(), change.value());
+      let line = line.trim_end_matches(['\n', '\r']);
+
+      match change.tag() {
+        ChangeTag::Delete => println!("{}", line.red()),
+        ChangeTag::Insert => println!("{}", line.green()),
+        ChangeTag::Equal => println!("{line}"),
+      }
+    }
+  }
+}
+
+/// Today's date as `YYYY-MM-DD`, in UTC.
+pub fn today() -> String {
+  let seconds = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |duration| duration.as_secs());
+
+  // Converts the days since epoch to a civil date, see
+  // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+  let z = (seconds / 86_400) as i64 + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z.rem_euclid(146_097);
+  let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + i64::from(month <= 2);
+
+  format!("{year:04}-{month:02}-{day:02}")
+}
@@ -1,39 +1,468 @@
-pub fn bump_version(version: &[u8], index: usize) -> Vec<u8> {
-  let mut version = version.to_vec();
+use anyhow::Result;
+use std::{cmp::Ordering, fmt, str::FromStr};
 
-  match index {
-    // major
-    0 => {
-      version[0] += 1;
-      version[1..].fill(0);
+/// A single dot-separated identifier of a pre-release.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Identifier {
+  Numeric(u64),
+  AlphaNumeric(String),
+}
+
+impl Ord for Identifier {
+  fn cmp(&self, other: &Self) -> Ordering {
+    match (self, other) {
+      (Self::Numeric(a), Self::Numeric(b)) => a.cmp(b),
+      (Self::AlphaNumeric(a), Self::AlphaNumeric(b)) => a.cmp(b),
+      // Numeric identifiers always have lower precedence than alphanumeric ones.
+      (Self::Numeric(_), Self::AlphaNumeric(_)) => Ordering::Less,
+      (Self::AlphaNumeric(_), Self::Numeric(_)) => Ordering::Greater,
     }
-    // minor
-    1 => {
-      version[1] += 1;
-      version[2..].fill(0);
+  }
+}
+
+impl PartialOrd for Identifier {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl fmt::Display for Identifier {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Numeric(value) => write!(f, "{value}"),
+      Self::AlphaNumeric(value) => write!(f, "{value}"),
     }
-    // patch
-    2 => version[2] += 1,
-    _ => unreachable!(),
   }
+}
 
-  version
+/// A version following the SemVer 2.0 specification.
+/// https://semver.org/spec/v2.0.0.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+  pub major: u64,
+  pub minor: u64,
+  pub patch: u64,
+  pub pre: Vec<Identifier>,
+  pub build: Vec<String>,
 }
 
-pub fn version_to_string(version: Vec<u8>) -> String {
-  version
-    .iter()
-    .map(|part| part.to_string())
-    .collect::<Vec<String>>()
-    .join(".")
+impl Version {
+  pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+    Self {
+      major,
+      minor,
+      patch,
+      pre: Vec::new(),
+      build: Vec::new(),
+    }
+  }
+
+  pub fn is_prerelease(&self) -> bool {
+    !self.pre.is_empty()
+  }
+
+  /// Returns the channel of the pre-release, e.g. `rc` for `2.0.0-rc.1`.
+  pub fn channel(&self) -> Option<&str> {
+    match self.pre.first() {
+      Some(Identifier::AlphaNumeric(channel)) => Some(channel),
+      _ => None,
+    }
+  }
+
+  /// Returns the same version without pre-release and build metadata.
+  pub fn stable(&self) -> Self {
+    Self::new(self.major, self.minor, self.patch)
+  }
+}
+
+impl Ord for Version {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self
+      .major
+      .cmp(&other.major)
+      .then(self.minor.cmp(&other.minor))
+      .then(self.patch.cmp(&other.patch))
+      .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+        // A pre-release always has a lower precedence than the normal version.
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => self.pre.cmp(&other.pre),
+      })
+      // Build metadata is not part of the precedence, we only use it
+      // to keep the ordering consistent with equality.
+      .then_with(|| self.build.cmp(&other.build))
+  }
+}
+
+impl PartialOrd for Version {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl fmt::Display for Version {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+
+    if !self.pre.is_empty() {
+      let pre = self.pre.iter().map(|identifier| identifier.to_string());
+      write!(f, "-{}", pre.collect::<Vec<_>>().join("."))?;
+    }
+
+    if !self.build.is_empty() {
+      write!(f, "+{}", self.build.join("."))?;
+    }
+
+    Ok(())
+  }
+}
+
+impl FromStr for Version {
+  type Err = anyhow::Error;
+
+  fn from_str(input: &str) -> Result<Self> {
+    let (rest, build) = match input.split_once('+') {
+      Some((rest, build)) => (rest, Some(build)),
+      None => (input, None),
+    };
+
+    let (core, pre) = match rest.split_once('-') {
+      Some((core, pre)) => (core, Some(pre)),
+      None => (rest, None),
+    };
+
+    let parts = core.split('.').collect::<Vec<_>>();
+    if parts.len() != 3 {
+      anyhow::bail!("invalid version '{input}', expected 'MAJOR.MINOR.PATCH'");
+    }
+
+    let major = parse_numeric(parts[0], input)?;
+    let minor = parse_numeric(parts[1], input)?;
+    let patch = parse_numeric(parts[2], input)?;
+
+    let pre = match pre {
+      Some(pre) => pre
+        .split('.')
+        .map(|identifier| parse_identifier(identifier, input))
+        .collect::<Result<Vec<_>>>()?,
+      None => Vec::new(),
+    };
+
+    let build = match build {
+      Some(build) => build
+        .split('.')
+        .map(|identifier| {
+          if !is_valid_identifier(identifier) {
+            anyhow::bail!("invalid build metadata '{identifier}' in version '{input}'");
+          }
+
+          Ok(identifier.to_string())
+        })
+        .collect::<Result<Vec<_>>>()?,
+      None => Vec::new(),
+    };
+
+    Ok(Self {
+      major,
+      minor,
+      patch,
+      pre,
+      build,
+    })
+  }
+}
+
+/// Identifiers are made of ASCII alphanumerics and hyphens and can't be empty.
+fn is_valid_identifier(identifier: &str) -> bool {
+  !identifier.is_empty()
+    && identifier
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Channels are the first identifier of a pre-release, a numeric one
+/// would be read back as a counter.
+pub fn is_valid_channel(channel: &str) -> bool {
+  is_valid_identifier(channel) && !channel.chars().all(|c| c.is_ascii_digit())
+}
+
+fn parse_numeric(part: &str, input: &str) -> Result<u64> {
+  if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+    anyhow::bail!("invalid number '{part}' in version '{input}'");
+  }
+
+  if part.len() > 1 && part.starts_with('0') {
+    anyhow::bail!("number '{part}' in version '{input}' must not have leading zeros");
+  }
+
+  part
+    .parse::<u64>()
+    .map_err(|_| anyhow::anyhow!("number '{part}' in version '{input}' is too large"))
 }
 
-pub fn string_to_version(version: &str) -> Vec<u8> {
+fn parse_identifier(identifier: &str, input: &str) -> Result<Identifier> {
+  if !is_valid_identifier(identifier) {
+    anyhow::bail!("invalid pre-release identifier '{identifier}' in version '{input}'");
+  }
+
+  if identifier.chars().all(|c| c.is_ascii_digit()) {
+    Ok(Identifier::Numeric(parse_numeric(identifier, input)?))
+  }
+  else {
+    Ok(Identifier::AlphaNumeric(identifier.to_string()))
+  }
+}
+
+/// Pre-release channels that can be picked when bumping.
+pub const CHANNELS: [&str; 3] = ["alpha", "beta", "rc"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Bump {
+  Major,
+  Minor,
+  Patch,
+  /// Bumps the major and starts a pre-release on the given channel.
+  Premajor(String),
+  /// Bumps the minor and starts a pre-release on the given channel.
+  Preminor(String),
+  /// Bumps the patch and starts a pre-release on the given channel.
+  Prepatch(String),
+  /// Moves to the next pre-release of the given channel.
+  Prerelease(String),
+  /// Promotes a pre-release to its stable version.
+  Release,
+}
+
+/// Bumps the given version, build metadata is always dropped.
+///
+/// Pre-release counters are computed from the given version and the
+/// `existing` versions - usually the tags - so `2.0.0-rc.2` comes after
+/// `2.0.0-rc.1` even if the current version is still `2.0.0-beta.3`.
+pub fn bump_version(version: &Version, bump: &Bump, existing: &[Version]) -> Version {
+  let pre = version.is_prerelease();
+
+  // Bumping a pre-release that already targets the next version
+  // only drops its pre-release part, e.g. `2.0.0-rc.1` to `2.0.0` for a major.
+  let major = || {
+    if pre && version.minor == 0 && version.patch == 0 {
+      version.stable()
+    }
+    else {
+      Version::new(version.major + 1, 0, 0)
+    }
+  };
+
+  let minor = || {
+    if pre && version.patch == 0 {
+      version.stable()
+    }
+    else {
+      Version::new(version.major, version.minor + 1, 0)
+    }
+  };
+
+  let patch = || {
+    if pre {
+      version.stable()
+    }
+    else {
+      Version::new(version.major, version.minor, version.patch + 1)
+    }
+  };
+
+  match bump {
+    Bump::Major => major(),
+    Bump::Minor => minor(),
+    Bump::Patch => patch(),
+    Bump::Premajor(channel) => {
+      let base = Version::new(version.major + 1, 0, 0);
+      prerelease(&base, channel, version, existing)
+    }
+    Bump::Preminor(channel) => {
+      let base = Version::new(version.major, version.minor + 1, 0);
+      prerelease(&base, channel, version, existing)
+    }
+    Bump::Prepatch(channel) => {
+      let base = Version::new(version.major, version.minor, version.patch + 1);
+      prerelease(&base, channel, version, existing)
+    }
+    Bump::Prerelease(channel) => prerelease(&patch(), channel, version, existing),
+    Bump::Release => version.stable(),
+  }
+}
+
+/// Builds the `<base>-<channel>.<n>` version where `n` follows the highest
+/// counter found for this base and channel in the current and existing versions.
+fn prerelease(base: &Version, channel: &str, current: &Version, existing: &[Version]) -> Version {
+  let counter = existing
+    .iter()
+    .chain(std::iter::once(current))
+    .filter(|version| version.stable() == *base && version.channel() == Some(channel))
+    .filter_map(|version| match version.pre.get(1) {
+      Some(Identifier::Numeric(counter)) => Some(*counter),
+      _ => None,
+    })
+    .max()
+    .map_or(1, |counter| counter + 1);
+
+  let mut version = base.clone();
+  version.pre = vec![
+    Identifier::AlphaNumeric(channel.to_string()),
+    Identifier::Numeric(counter),
+  ];
+
   version
-    .split(".")
-    .map(|part| part.parse::<u8>().unwrap())
-    .collect()
+}
+
+pub fn version_to_string(version: &Version) -> String {
+  version.to_string()
+}
+
+pub fn string_to_version(version: &str) -> Result<Version> {
+  version.trim().parse()
 }
 
 /// Whenever the project has no version so we default on this version.
 pub const DEFAULT: &str = "0.0.0";
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn version(version: &str) -> Version {
+    string_to_version(version).unwrap()
+  }
+
+  fn bump(from: &str, bump: Bump, existing: &[&str]) -> String {
+    let existing = existing.iter().map(|v| version(v)).collect::<Vec<_>>();
+    bump_version(&version(from), &bump, &existing).to_string()
+  }
+
+  #[test]
+  fn rejects_invalid_versions() {
+    for invalid in [
+      "01.0.0",
+      "1.00.0",
+      "1.0.0-01",
+      "1.0.0-",
+      "1.0.0-alpha..1",
+      "1.0.0+",
+      "1.0.0+build..1",
+      "1.0.0-alpha_1",
+      "1.2.3.4",
+      "1.2",
+      "",
+      "a.b.c",
+      "18446744073709551616.0.0",
+    ] {
+      assert!(
+        string_to_version(invalid).is_err(),
+        "{invalid} should be rejected"
+      );
+    }
+  }
+
+  #[test]
+  fn display_round_trips() {
+    for valid in [
+      "0.0.0",
+      "1.2.3",
+      "1.0.0-alpha",
+      "1.0.0-alpha.1",
+      "1.0.0-0.3.7",
+      "1.0.0-x-y-z.--",
+      "1.0.0+20130313144700",
+      "1.0.0-beta+exp.sha.5114f85",
+      "18446744073709551615.0.0",
+    ] {
+      assert_eq!(version(valid).to_string(), valid);
+    }
+  }
+
+  #[test]
+  fn follows_the_spec_precedence() {
+    let ordered = [
+      "1.0.0-alpha",
+      "1.0.0-alpha.1",
+      "1.0.0-alpha.beta",
+      "1.0.0-beta",
+      "1.0.0-beta.2",
+      "1.0.0-beta.11",
+      "1.0.0-rc.1",
+      "1.0.0",
+      "1.0.1",
+      "1.1.0",
+      "2.0.0",
+    ];
+
+    for pair in ordered.windows(2) {
+      assert!(
+        version(pair[0]) < version(pair[1]),
+        "{} < {}",
+        pair[0],
+        pair[1]
+      );
+    }
+  }
+
+  #[test]
+  fn bumps_stable_versions() {
+    assert_eq!(bump("1.2.3", Bump::Major, &[]), "2.0.0");
+    assert_eq!(bump("1.2.3", Bump::Minor, &[]), "1.3.0");
+    assert_eq!(bump("1.2.3", Bump::Patch, &[]), "1.2.4");
+    assert_eq!(bump("1.2.3+build.1", Bump::Patch, &[]), "1.2.4");
+  }
+
+  #[test]
+  fn bumps_prereleases_to_their_stable_version() {
+    assert_eq!(bump("2.0.0-rc.1", Bump::Major, &[]), "2.0.0");
+    assert_eq!(bump("1.2.0-rc.1", Bump::Major, &[]), "2.0.0");
+    assert_eq!(bump("1.2.0-rc.1", Bump::Minor, &[]), "1.2.0");
+    assert_eq!(bump("1.2.3-rc.1", Bump::Minor, &[]), "1.3.0");
+    assert_eq!(bump("1.2.3-rc.1", Bump::Patch, &[]), "1.2.3");
+    assert_eq!(bump("1.2.3-rc.1", Bump::Release, &[]), "1.2.3");
+  }
+
+  #[test]
+  fn counts_prereleases() {
+    assert_eq!(
+      bump("1.2.3", Bump::Premajor("alpha".into()), &[]),
+      "2.0.0-alpha.1"
+    );
+    assert_eq!(
+      bump("1.2.3", Bump::Preminor("beta".into()), &[]),
+      "1.3.0-beta.1"
+    );
+    assert_eq!(
+      bump("1.2.3", Bump::Prepatch("rc".into()), &[]),
+      "1.2.4-rc.1"
+    );
+    assert_eq!(
+      bump("1.2.3", Bump::Prerelease("rc".into()), &[]),
+      "1.2.4-rc.1"
+    );
+    assert_eq!(
+      bump("2.0.0-rc.1", Bump::Prerelease("rc".into()), &[]),
+      "2.0.0-rc.2"
+    );
+  }
+
+  #[test]
+  fn counts_prereleases_from_existing_versions() {
+    let existing = ["2.0.0-rc.1", "2.0.0-rc.3", "2.0.0-beta.7", "3.0.0-rc.9"];
+
+    assert_eq!(
+      bump("2.0.0-beta.3", Bump::Prerelease("rc".into()), &existing),
+      "2.0.0-rc.4"
+    );
+    assert_eq!(
+      bump("1.5.0", Bump::Premajor("beta".into()), &existing),
+      "2.0.0-beta.8"
+    );
+    assert_eq!(
+      bump("1.5.0", Bump::Premajor("alpha".into()), &existing),
+      "2.0.0-alpha.1"
+    );
+  }
+}